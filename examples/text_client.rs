@@ -83,10 +83,8 @@ impl eframe::App for ArchipelagoClient {
             ui.separator();
 
             match self.connection.state_mut() {
-                ap::ConnectionState::Disconnected(_) => {
-                    if ui.button("Connect").clicked() {
-                        self.connect_popup.visible = true;
-                    }
+                ap::ConnectionState::Disconnected(_) if ui.button("Connect").clicked() => {
+                    self.connect_popup.visible = true;
                 }
                 ap::ConnectionState::Connected(client) => {
                     ScrollArea::vertical()
@@ -1,57 +1,98 @@
-use smol::fs;
-use std::{env, path::PathBuf};
+use smol::io;
+use std::{env, ffi::OsString, path::PathBuf, sync::Arc};
 use ustr::UstrMap;
 
-use crate::{protocol::GameData, util};
+use crate::protocol::GameData;
+
+mod backend;
+mod disk;
+mod memory;
+
+pub use backend::*;
+use disk::*;
+pub use memory::*;
 
 /// The cache in which we store data packages to avoid requesting them every
 /// time the client starts.
-pub struct Cache(PathBuf);
+///
+/// By default this stores data packages on disk, but it can use any
+/// [CacheBackend].
+#[derive(Clone)]
+pub struct Cache(Arc<dyn CacheBackend>);
 
 impl Cache {
     /// Returns a cache that uses Archipelago's system-wide shared directory.
     /// This allows the client to share datapackages with other games, even if
     /// they use other client libraries.
+    ///
+    /// If the platform has no known cache directory, this falls back to the
+    /// system's temporary directory. If the cache directory can't be read from
+    /// or written to, the client will still connect successfully but will
+    /// download data packages from the server every time.
     pub fn shared() -> Self {
-        Self(Self::platform_cache_dir().unwrap_or_else(|| {
-            env::current_dir()
-                .expect("failed to determine current working directory")
-                .join("Archipelago")
-                .join("Cache")
-        }))
+        Self::path(
+            Self::platform_cache_dir()
+                .unwrap_or_else(|| env::temp_dir().join("Archipelago").join("Cache")),
+        )
     }
 
     /// Returns a cache that uses a custom filesystem path.
     pub fn path(path: impl Into<PathBuf>) -> Self {
-        Self(path.into())
+        Self::custom(DiskCacheBackend::new(path.into()))
+    }
+
+    /// Returns a cache that stores data packages in memory, using a new
+    /// [MemoryCacheBackend].
+    ///
+    /// Clones of this cache share the same storage.
+    pub fn memory() -> Self {
+        Self::custom(MemoryCacheBackend::new())
+    }
+
+    /// Returns a cache that stores data packages using `backend`.
+    pub fn custom(backend: impl CacheBackend + 'static) -> Self {
+        Self(Arc::new(backend))
     }
 
     /// Returns the default Archipelago cache directory for the current
     /// operating system.
     fn platform_cache_dir() -> Option<PathBuf> {
+        Self::platform_cache_dir_with_env(|name| env::var_os(name))
+    }
+
+    /// Like [platform_cache_dir](Self::platform_cache_dir), but looks up
+    /// environment variables using `var` rather than the process environment.
+    fn platform_cache_dir_with_env(var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
         #[cfg(target_os = "windows")]
         {
-            env::var_os("LOCALAPPDATA")
+            var("LOCALAPPDATA")
                 .map(PathBuf::from)
                 .map(|p| p.join("Archipelago").join("Cache"))
         }
 
+        // These match the paths Python's platformdirs library returns for
+        // `user_cache_dir("Archipelago", False)`, which is what the official
+        // client uses. Only Windows adds a "Cache" subdirectory.
+
         #[cfg(target_os = "macos")]
         {
-            env::var_os("HOME").map(PathBuf::from).map(|h| {
-                h.join("Library")
-                    .join("Caches")
-                    .join("Archipelago")
-                    .join("Cache")
-            })
+            var("HOME")
+                .filter(|h| !h.is_empty())
+                .map(PathBuf::from)
+                .map(|h| h.join("Library").join("Caches").join("Archipelago"))
         }
 
         #[cfg(target_os = "linux")]
         {
-            env::var_os("XDG_CACHE_HOME")
+            var("XDG_CACHE_HOME")
+                .filter(|p| !p.is_empty())
                 .map(PathBuf::from)
-                .or_else(|| env::home_dir().map(|h| h.join(".cache")))
-                .map(|p| p.join("Archipelago").join("Cache"))
+                .or_else(|| {
+                    var("HOME")
+                        .filter(|h| !h.is_empty())
+                        .map(|h| PathBuf::from(h).join(".cache"))
+                })
+                .map(|p| p.join("Archipelago"))
         }
     }
 
@@ -67,16 +108,15 @@ impl Cache {
     ) -> UstrMap<GameData> {
         let mut data_packages =
             UstrMap::with_capacity_and_hasher(checksums.len(), Default::default());
-        let dir = self.data_package_path();
         for (game, checksum) in checksums {
-            let path = dir
-                .join(util::sanitize_file_name(game))
-                .join(util::sanitize_file_name(format!("{checksum}.json")));
-
-            let file = match fs::read_to_string(&path).await {
-                Ok(f) => f,
+            let file = match self.0.load(game, checksum).await {
+                Ok(Some(file)) => file,
+                Ok(None) => {
+                    log::debug!("No cached data package for {game}");
+                    continue;
+                }
                 Err(err) => {
-                    log::error!("Missing or unreadable cache for {}: {}", game, err);
+                    log::warn!("Failed to read cached data package for {game}: {err}");
                     continue;
                 }
             };
@@ -102,17 +142,11 @@ impl Cache {
     /// Stores `data_packages`, a map from game names to data packages, in the
     /// cache.
     pub(crate) async fn store_data_packages(&self, data_packages: &UstrMap<GameData>) {
-        let dir = self.data_package_path();
         for (game, data) in data_packages {
-            let game_dir = dir.join(util::sanitize_file_name(game));
-
-            if let Err(err) = fs::create_dir_all(&game_dir).await {
-                log::error!("Failed to create cache directory {game_dir:?}: {err}");
-                // If one directory fails to create, chances are the others will
-                // as well.
-                return;
-            }
-
+            // This includes everything in [GameData], including name groups,
+            // so cached entries are indistinguishable from downloaded ones. We
+            // never compute checksums ourselves, so there's nothing else to
+            // keep in sync: the checksum is whatever the server reported.
             let serialized = match serde_json::to_string(&data) {
                 Ok(r) => r,
                 Err(err) => {
@@ -121,19 +155,37 @@ impl Cache {
                 }
             };
 
-            let path = game_dir.join(util::sanitize_file_name(format!("{}.json", data.checksum)));
-            if let Err(err) = util::write_file_atomic(&path, serialized).await {
-                log::error!("Failed to write cached data package to {path:?}: {err}");
+            if let Err(err) = self.0.store(game, &data.checksum, serialized).await {
+                log::warn!("Failed to cache data package for {game}, skipping the rest: {err}");
+                // If one entry fails to store, chances are the others will as
+                // well.
+                return;
             }
         }
     }
 
-    /// Returns the subdirectory that should contain datapackages.
-    fn data_package_path(&self) -> PathBuf {
-        // We could just use this as the root of the cache, but this is more
-        // forward-compatible with the possibility of caching other data in the
-        // future.
-        self.0.join("datapackage")
+    /// Deletes all cached data packages other than those in `keep`.
+    ///
+    /// `keep` is a map from game names to the checksums of the data packages to
+    /// keep for those games. Games that don't appear in `keep` at all have all
+    /// their data packages deleted.
+    ///
+    /// If some entries can't be deleted, this still deletes as many others as
+    /// it can before returning the first error.
+    pub async fn prune(&self, keep: &UstrMap<String>) -> io::Result<()> {
+        self.0.prune(keep).await
+    }
+
+    /// Returns statistics about the data currently stored in this cache.
+    ///
+    /// If the cache directory doesn't exist yet, this returns empty stats.
+    pub async fn stats(&self) -> io::Result<CacheStats> {
+        self.0.stats().await
+    }
+
+    /// Deletes all data cached in this cache.
+    pub async fn clear(&self) -> io::Result<()> {
+        self.0.clear().await
     }
 }
 
@@ -143,3 +195,212 @@ impl Default for Cache {
         Cache::shared()
     }
 }
+
+/// Statistics about the data stored in a [Cache], as returned by
+/// [Cache::stats].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheStats {
+    games: usize,
+    total_bytes: u64,
+}
+
+impl CacheStats {
+    /// Creates statistics for a cache. This is useful for implementing
+    /// [CacheBackend::stats].
+    pub fn new(games: usize, total_bytes: u64) -> Self {
+        CacheStats { games, total_bytes }
+    }
+
+    /// The number of games that have at least one cached data package.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// The total size of all cached data packages, in bytes.
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Barrier;
+    use std::thread;
+
+    use serde_json::json;
+    use ustr::ustr;
+
+    use super::*;
+    use crate::util::TestDir;
+
+    /// Returns a [GameData] with a single item and location and the given
+    /// `checksum`.
+    fn game_data(checksum: &str) -> GameData {
+        serde_json::from_value(json!({
+            "item_name_to_id": {"Item": 1},
+            "location_name_to_id": {"Location": 2},
+            "item_name_groups": {"Group": ["Item"]},
+            "checksum": checksum,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn data_packages_round_trip() {
+        let cache = Cache::memory();
+        smol::block_on(async {
+            let stored = UstrMap::from_iter([(ustr("Game"), game_data("abc"))]);
+            cache.store_data_packages(&stored).await;
+
+            let loaded = cache
+                .load_data_packages(&UstrMap::from_iter([(ustr("Game"), "abc".into())]))
+                .await;
+            let data = &loaded[&ustr("Game")];
+            assert_eq!(data.checksum, "abc");
+            assert_eq!(data.item_name_to_id[&ustr("Item")], 1);
+            assert_eq!(data.location_name_to_id[&ustr("Location")], 2);
+            assert_eq!(data.item_name_groups[&ustr("Group")], [ustr("Item")]);
+        });
+    }
+
+    #[test]
+    fn data_packages_round_trip_on_disk() {
+        let dir = TestDir::new();
+        let cache = Cache::path(dir.path());
+        smol::block_on(async {
+            let stored = UstrMap::from_iter([(ustr("Game"), game_data("abc"))]);
+            cache.store_data_packages(&stored).await;
+
+            // A fresh cache over the same directory sees the name groups that
+            // were written to disk.
+            let loaded = Cache::path(dir.path())
+                .load_data_packages(&UstrMap::from_iter([(ustr("Game"), "abc".into())]))
+                .await;
+            let data = &loaded[&ustr("Game")];
+            assert_eq!(data.checksum, "abc");
+            assert_eq!(data.item_name_groups[&ustr("Group")], [ustr("Item")]);
+        });
+    }
+
+    #[test]
+    fn concurrent_stores_for_the_same_game() {
+        let dir = TestDir::new();
+        // Make the data package big enough that the writes are likely to
+        // overlap.
+        let mut data = game_data("abc");
+        for i in 0..10_000 {
+            data.item_name_to_id
+                .insert(ustr(&format!("Item {i}")), i + 10);
+        }
+        let stored = Arc::new(UstrMap::from_iter([(ustr("Game"), data)]));
+
+        let barrier = Arc::new(Barrier::new(2));
+        let writers = (0..2)
+            .map(|_| {
+                let cache = Cache::path(dir.path());
+                let stored = Arc::clone(&stored);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    smol::block_on(cache.store_data_packages(&stored));
+                })
+            })
+            .collect::<Vec<_>>();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let game_dir = dir.path().join("datapackage").join("Game");
+        let contents = std::fs::read_to_string(game_dir.join("abc.json")).unwrap();
+        let data = serde_json::from_str::<GameData>(&contents).unwrap();
+        assert_eq!(data.checksum, "abc");
+        assert_eq!(data.item_name_to_id.len(), 10_001);
+        // Both writers' temporary files were renamed into place.
+        assert_eq!(std::fs::read_dir(game_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn load_data_packages_skips_mismatched_checksums() {
+        let backend = MemoryCacheBackend::new();
+        smol::block_on(async {
+            // Store a package whose contents don't match the checksum it's
+            // filed under.
+            let contents = serde_json::to_string(&game_data("other")).unwrap();
+            backend.store("Game", "abc", contents).await.unwrap();
+            backend.store("Broken", "abc", "{".into()).await.unwrap();
+
+            let cache = Cache::custom(backend);
+            let loaded = cache
+                .load_data_packages(&UstrMap::from_iter([
+                    (ustr("Game"), "abc".into()),
+                    (ustr("Broken"), "abc".into()),
+                    (ustr("Missing"), "abc".into()),
+                ]))
+                .await;
+            assert!(loaded.is_empty());
+        });
+    }
+
+    /// Returns an environment lookup function that only knows about `vars`.
+    #[cfg(target_os = "linux")]
+    fn fake_env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.into())
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn platform_cache_dir_prefers_xdg_cache_home() {
+        assert_eq!(
+            Cache::platform_cache_dir_with_env(fake_env(&[
+                ("XDG_CACHE_HOME", "/xdg/cache"),
+                ("HOME", "/home/user"),
+            ])),
+            Some(PathBuf::from("/xdg/cache/Archipelago"))
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn platform_cache_dir_falls_back_to_home() {
+        let expected = Some(PathBuf::from("/home/user/.cache/Archipelago"));
+        assert_eq!(
+            Cache::platform_cache_dir_with_env(fake_env(&[("HOME", "/home/user")])),
+            expected
+        );
+        assert_eq!(
+            Cache::platform_cache_dir_with_env(fake_env(&[
+                ("XDG_CACHE_HOME", ""),
+                ("HOME", "/home/user"),
+            ])),
+            expected
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn platform_cache_dir_without_home() {
+        assert_eq!(Cache::platform_cache_dir_with_env(fake_env(&[])), None);
+        assert_eq!(
+            Cache::platform_cache_dir_with_env(fake_env(&[("HOME", "")])),
+            None
+        );
+    }
+
+    #[test]
+    fn clones_share_storage() {
+        let cache = Cache::memory();
+        let clone = cache.clone();
+        smol::block_on(async {
+            let stored = UstrMap::from_iter([(ustr("Game"), game_data("abc"))]);
+            cache.store_data_packages(&stored).await;
+            assert_eq!(clone.stats().await.unwrap().games(), 1);
+
+            clone.clear().await.unwrap();
+            assert_eq!(cache.stats().await.unwrap().games(), 0);
+        });
+    }
+}
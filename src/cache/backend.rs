@@ -0,0 +1,53 @@
+use std::{future::Future, pin::Pin};
+
+use smol::io;
+use ustr::UstrMap;
+
+use crate::CacheStats;
+
+/// A boxed future returned by [CacheBackend] methods.
+pub type CacheFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A storage mechanism for a [Cache](crate::Cache).
+///
+/// Each entry is a serialized data package, identified by the name of its game
+/// and its checksum. Backends don't need to validate entries, since the cache
+/// double-checks checksums when it loads them.
+///
+/// Only [load](Self::load) and [store](Self::store) are required. The other
+/// methods return [io::ErrorKind::Unsupported] errors by default.
+pub trait CacheBackend: Send + Sync {
+    /// Returns the serialized data package for `game` with `checksum`, or
+    /// `None` if there's no such entry.
+    fn load<'a>(
+        &'a self,
+        game: &'a str,
+        checksum: &'a str,
+    ) -> CacheFuture<'a, io::Result<Option<String>>>;
+
+    /// Stores `contents` as the serialized data package for `game` with
+    /// `checksum`.
+    fn store<'a>(
+        &'a self,
+        game: &'a str,
+        checksum: &'a str,
+        contents: String,
+    ) -> CacheFuture<'a, io::Result<()>>;
+
+    /// Deletes all entries other than those in `keep`. See
+    /// [Cache::prune](crate::Cache::prune).
+    fn prune<'a>(&'a self, keep: &'a UstrMap<String>) -> CacheFuture<'a, io::Result<()>> {
+        let _ = keep;
+        Box::pin(async { Err(io::ErrorKind::Unsupported.into()) })
+    }
+
+    /// Deletes all entries.
+    fn clear(&self) -> CacheFuture<'_, io::Result<()>> {
+        Box::pin(async { Err(io::ErrorKind::Unsupported.into()) })
+    }
+
+    /// Returns statistics about the stored entries.
+    fn stats(&self) -> CacheFuture<'_, io::Result<CacheStats>> {
+        Box::pin(async { Err(io::ErrorKind::Unsupported.into()) })
+    }
+}
@@ -0,0 +1,271 @@
+use std::{collections::HashMap, mem, path::PathBuf};
+
+use smol::{fs, io, stream::StreamExt};
+use ustr::UstrMap;
+
+use crate::{CacheBackend, CacheFuture, CacheStats, util};
+
+/// A [CacheBackend] that stores data packages as JSON files in a directory on
+/// disk, using the same layout as the official Archipelago client.
+pub(crate) struct DiskCacheBackend(PathBuf);
+
+impl DiskCacheBackend {
+    /// Creates a backend that stores data packages beneath `root`.
+    pub(crate) fn new(root: PathBuf) -> Self {
+        // We could just use this as the root of the cache, but this is more
+        // forward-compatible with the possibility of caching other data in the
+        // future.
+        Self(root.join("datapackage"))
+    }
+
+    /// Returns the path of the file for `game`'s data package with `checksum`.
+    fn entry_path(&self, game: &str, checksum: &str) -> PathBuf {
+        self.0
+            .join(util::sanitize_file_name(game))
+            .join(util::sanitize_file_name(format!("{checksum}.json")))
+    }
+}
+
+impl CacheBackend for DiskCacheBackend {
+    fn load<'a>(
+        &'a self,
+        game: &'a str,
+        checksum: &'a str,
+    ) -> CacheFuture<'a, io::Result<Option<String>>> {
+        Box::pin(async move {
+            match fs::read_to_string(self.entry_path(game, checksum)).await {
+                Ok(contents) => Ok(Some(contents)),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err),
+            }
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        game: &'a str,
+        checksum: &'a str,
+        contents: String,
+    ) -> CacheFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let path = self.entry_path(game, checksum);
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).await?;
+            }
+            util::write_file_atomic(&path, contents).await
+        })
+    }
+
+    fn prune<'a>(&'a self, keep: &'a UstrMap<String>) -> CacheFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let keep = keep
+                .iter()
+                .map(|(game, checksum)| {
+                    (
+                        util::sanitize_file_name(game),
+                        util::sanitize_file_name(format!("{checksum}.json")),
+                    )
+                })
+                .collect::<HashMap<_, _>>();
+
+            let mut games = match fs::read_dir(&self.0).await {
+                Ok(games) => games,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+                Err(err) => return Err(err),
+            };
+
+            // This directory may be shared with other clients, so keep going
+            // if we fail to delete any individual entry and report the first
+            // error at the end.
+            let mut first_error = None;
+            while let Some(game_dir) = games.try_next().await? {
+                if let Err(err) = prune_game_dir(&game_dir, &keep).await {
+                    log::warn!("Failed to prune {}: {err}", game_dir.path().display());
+                    first_error.get_or_insert(err);
+                }
+            }
+            first_error.map_or(Ok(()), Err)
+        })
+    }
+
+    fn clear(&self) -> CacheFuture<'_, io::Result<()>> {
+        Box::pin(async move {
+            match fs::remove_dir_all(&self.0).await {
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            }
+        })
+    }
+
+    fn stats(&self) -> CacheFuture<'_, io::Result<CacheStats>> {
+        Box::pin(async move {
+            let mut games = match fs::read_dir(&self.0).await {
+                Ok(games) => games,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    return Ok(Default::default());
+                }
+                Err(err) => return Err(err),
+            };
+
+            let mut game_count = 0;
+            let mut total_bytes = 0;
+            while let Some(game_dir) = games.try_next().await? {
+                if !game_dir.file_type().await?.is_dir() {
+                    continue;
+                }
+
+                let mut has_files = false;
+                let mut files = fs::read_dir(game_dir.path()).await?;
+                while let Some(file) = files.try_next().await? {
+                    let metadata = file.metadata().await?;
+                    if metadata.is_file() {
+                        has_files = true;
+                        total_bytes += metadata.len();
+                    }
+                }
+                if has_files {
+                    game_count += 1;
+                }
+            }
+            Ok(CacheStats::new(game_count, total_bytes))
+        })
+    }
+}
+
+/// Deletes all the data packages in `game_dir` other than the one in `keep`. If
+/// `keep` has no entry for this game, deletes the directory as well if it's
+/// empty afterwards.
+///
+/// Ignores `game_dir` if it isn't a directory, and never deletes temporary
+/// files, since those may belong to other processes' in-progress writes.
+async fn prune_game_dir(game_dir: &fs::DirEntry, keep: &HashMap<String, String>) -> io::Result<()> {
+    if !game_dir.file_type().await?.is_dir() {
+        return Ok(());
+    }
+
+    let checksum = game_dir.file_name().to_str().and_then(|g| keep.get(g));
+    let mut first_error = None;
+    let mut files = fs::read_dir(game_dir.path()).await?;
+    while let Some(file) = files.try_next().await? {
+        let name = file.file_name();
+        if util::is_temp_file(&name)
+            || checksum.is_some_and(|checksum| name.to_str() == Some(checksum.as_str()))
+            || !file.file_type().await?.is_file()
+        {
+            continue;
+        }
+
+        if let Err(err) = fs::remove_file(file.path()).await {
+            first_error.get_or_insert(err);
+        }
+    }
+
+    if checksum.is_none() {
+        // This fails if the directory still contains temporary files or
+        // anything else we didn't create, in which case we leave it be.
+        mem::drop(fs::remove_dir(game_dir.path()).await);
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ustr::ustr;
+
+    use super::*;
+    use crate::util::TestDir;
+
+    #[test]
+    fn prune_keeps_only_requested_entries() {
+        let dir = TestDir::new();
+        let root = dir.path().join("datapackage");
+        fs::create_dir_all(root.join("Kept")).unwrap();
+        fs::create_dir_all(root.join("Removed")).unwrap();
+        fs::write(root.join("Kept").join("new.json"), "{}").unwrap();
+        fs::write(root.join("Kept").join("old.json"), "{}").unwrap();
+        fs::write(root.join("Kept").join("old-tmp-1234.json"), "{}").unwrap();
+        fs::write(root.join("Removed").join("old.json"), "{}").unwrap();
+        fs::write(root.join("stray"), "").unwrap();
+
+        let backend = DiskCacheBackend::new(dir.path().to_path_buf());
+        let keep = UstrMap::from_iter([(ustr("Kept"), "new".to_string())]);
+        smol::block_on(backend.prune(&keep)).unwrap();
+
+        assert!(root.join("Kept").join("new.json").exists());
+        assert!(!root.join("Kept").join("old.json").exists());
+        assert!(root.join("Kept").join("old-tmp-1234.json").exists());
+        assert!(!root.join("Removed").exists());
+        assert!(root.join("stray").exists());
+    }
+
+    #[test]
+    fn prune_leaves_directories_with_temp_files() {
+        let dir = TestDir::new();
+        let game = dir.path().join("datapackage").join("Game");
+        fs::create_dir_all(&game).unwrap();
+        fs::write(game.join("old.json"), "{}").unwrap();
+        fs::write(game.join("new-tmp-1234.json"), "{}").unwrap();
+
+        let backend = DiskCacheBackend::new(dir.path().to_path_buf());
+        smol::block_on(backend.prune(&Default::default())).unwrap();
+
+        assert!(!game.join("old.json").exists());
+        assert!(game.join("new-tmp-1234.json").exists());
+    }
+
+    #[test]
+    fn stats_counts_games_and_bytes() {
+        let dir = TestDir::new();
+        let root = dir.path().join("datapackage");
+        fs::create_dir_all(root.join("One")).unwrap();
+        fs::create_dir_all(root.join("Two")).unwrap();
+        fs::create_dir_all(root.join("Empty")).unwrap();
+        fs::write(root.join("One").join("a.json"), "12345").unwrap();
+        fs::write(root.join("One").join("b.json"), "123").unwrap();
+        fs::write(root.join("Two").join("c.json"), "12").unwrap();
+        fs::write(root.join("stray"), "123456789").unwrap();
+
+        let backend = DiskCacheBackend::new(dir.path().to_path_buf());
+        let stats = smol::block_on(backend.stats()).unwrap();
+        assert_eq!(stats.games(), 2);
+        assert_eq!(stats.total_bytes(), 10);
+    }
+
+    #[test]
+    fn stats_missing_directory() {
+        let dir = TestDir::new();
+        let backend = DiskCacheBackend::new(dir.path().join("missing"));
+        let stats = smol::block_on(backend.stats()).unwrap();
+        assert_eq!(stats.games(), 0);
+        assert_eq!(stats.total_bytes(), 0);
+    }
+
+    #[test]
+    fn store_and_load() {
+        let dir = TestDir::new();
+        let backend = DiskCacheBackend::new(dir.path().to_path_buf());
+        smol::block_on(async {
+            assert_eq!(backend.load("Game", "abc").await.unwrap(), None);
+            backend.store("Game", "abc", "{}".into()).await.unwrap();
+            assert_eq!(
+                backend.load("Game", "abc").await.unwrap().as_deref(),
+                Some("{}")
+            );
+            assert_eq!(backend.load("Game", "def").await.unwrap(), None);
+
+            backend.clear().await.unwrap();
+            assert_eq!(backend.load("Game", "abc").await.unwrap(), None);
+            // Clearing an already-empty cache is fine.
+            backend.clear().await.unwrap();
+        });
+    }
+
+    #[test]
+    fn prune_missing_directory() {
+        let dir = TestDir::new();
+        let backend = DiskCacheBackend::new(dir.path().join("missing"));
+        smol::block_on(backend.prune(&Default::default())).unwrap();
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use smol::io;
+use ustr::{Ustr, UstrMap};
+
+use crate::{CacheBackend, CacheFuture, CacheStats};
+
+/// A [CacheBackend] that stores data packages in memory.
+///
+/// This is useful on platforms without a filesystem, or to share downloaded
+/// data packages between multiple connections in the same process without
+/// touching the disk.
+#[derive(Default)]
+pub struct MemoryCacheBackend(Mutex<HashMap<(Ustr, String), String>>);
+
+impl MemoryCacheBackend {
+    /// Creates an empty in-memory cache backend.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl CacheBackend for MemoryCacheBackend {
+    fn load<'a>(
+        &'a self,
+        game: &'a str,
+        checksum: &'a str,
+    ) -> CacheFuture<'a, io::Result<Option<String>>> {
+        let entries = self.0.lock().unwrap();
+        let result = entries.get(&(game.into(), checksum.into())).cloned();
+        Box::pin(async { Ok(result) })
+    }
+
+    fn store<'a>(
+        &'a self,
+        game: &'a str,
+        checksum: &'a str,
+        contents: String,
+    ) -> CacheFuture<'a, io::Result<()>> {
+        let mut entries = self.0.lock().unwrap();
+        entries.insert((game.into(), checksum.into()), contents);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn prune<'a>(&'a self, keep: &'a UstrMap<String>) -> CacheFuture<'a, io::Result<()>> {
+        let mut entries = self.0.lock().unwrap();
+        entries.retain(|(game, checksum), _| keep.get(game) == Some(checksum));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn clear(&self) -> CacheFuture<'_, io::Result<()>> {
+        self.0.lock().unwrap().clear();
+        Box::pin(async { Ok(()) })
+    }
+
+    fn stats(&self) -> CacheFuture<'_, io::Result<CacheStats>> {
+        let entries = self.0.lock().unwrap();
+        let games = entries
+            .keys()
+            .map(|(game, _)| *game)
+            .collect::<HashSet<_>>();
+        let total_bytes = entries.values().map(|c| c.len() as u64).sum();
+        let stats = CacheStats::new(games.len(), total_bytes);
+        Box::pin(async move { Ok(stats) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ustr::ustr;
+
+    use super::*;
+
+    #[test]
+    fn store_and_load() {
+        let backend = MemoryCacheBackend::new();
+        smol::block_on(async {
+            assert_eq!(backend.load("Game", "abc").await.unwrap(), None);
+            backend.store("Game", "abc", "{}".into()).await.unwrap();
+            assert_eq!(
+                backend.load("Game", "abc").await.unwrap().as_deref(),
+                Some("{}")
+            );
+            assert_eq!(backend.load("Game", "def").await.unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn prune_clear_and_stats() {
+        let backend = MemoryCacheBackend::new();
+        smol::block_on(async {
+            backend.store("One", "old", "123".into()).await.unwrap();
+            backend.store("One", "new", "12345".into()).await.unwrap();
+            backend.store("Two", "abc", "12".into()).await.unwrap();
+
+            let stats = backend.stats().await.unwrap();
+            assert_eq!(stats.games(), 2);
+            assert_eq!(stats.total_bytes(), 10);
+
+            let keep = UstrMap::from_iter([(ustr("One"), "new".to_string())]);
+            backend.prune(&keep).await.unwrap();
+            assert_eq!(backend.load("One", "old").await.unwrap(), None);
+            assert!(backend.load("One", "new").await.unwrap().is_some());
+            assert_eq!(backend.load("Two", "abc").await.unwrap(), None);
+
+            backend.clear().await.unwrap();
+            let stats = backend.stats().await.unwrap();
+            assert_eq!(stats.games(), 0);
+            assert_eq!(stats.total_bytes(), 0);
+        });
+    }
+}
@@ -1,12 +1,14 @@
 use serde::de::DeserializeOwned;
-use std::collections::{HashMap, VecDeque};
-use std::{mem, ptr, sync::Arc, time::SystemTime};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant, SystemTime};
+use std::{mem, pin::Pin, ptr, sync::Arc};
 use ustr::{Ustr, UstrMap, UstrSet};
 
 use crate::{
-    ArgumentError, AsLocationId, ConnectionOptions, Error, Event, Game, ItemHandling, Iter,
-    LocatedItem, Location, Player, Print, ProtocolError, ReceivedItem, SignedDuration, Socket,
-    UnsizedIter, UpdatedField, Version, protocol::*,
+    ARCHIPELAGO_NAME, ArgumentError, AsLocationId, Cache, ConnectingPhase, ConnectingProgress,
+    ConnectionOptions, Error, Event, Game, Hint, ItemHandling, Iter, LocatedItem, Location, Player,
+    Print, ProtocolError, ReceivedItem, ScoutSummary, SignedDuration, Socket, Transport,
+    UnsizedIter, UpdatedField, Version, protocol::*, try_future,
 };
 
 mod bounce_options;
@@ -25,6 +27,57 @@ const VERSION: NetworkVersion = NetworkVersion {
     class: String::new(),
 };
 
+/// The oldest generator version whose multiworlds this client is known to
+/// handle correctly.
+const MIN_GENERATOR_VERSION: Version = Version::new(0, 5, 0);
+
+/// The newest generator version whose multiworlds this client is known to
+/// handle correctly.
+const MAX_GENERATOR_VERSION: Version = Version::new(0, 6, u16::MAX);
+
+/// The newest server version this client is known to handle correctly. Newer
+/// patch releases are assumed to be compatible.
+const MAX_SERVER_VERSION: Version = Version::new(0, 6, u16::MAX);
+
+/// Deserializes the raw slot data from the server into `S`.
+pub(crate) fn deserialize_slot_data<S: DeserializeOwned>(
+    value: &serde_json::Value,
+) -> Result<S, Error> {
+    S::deserialize(value).map_err(|error| {
+        ProtocolError::Deserialize {
+            json: value.to_string(),
+            error,
+        }
+        .into()
+    })
+}
+
+/// A callback registered with [Client.on_event].
+type EventCallback = Box<dyn FnMut(&Event) + Send>;
+
+/// A pending response to a `Get` request.
+enum GetSender {
+    /// A request from [Client.get], which receives the raw values.
+    Raw(oneshot::Sender<Result<HashMap<String, serde_json::Value>, Error>>),
+
+    /// A request from [Client.get_hints_for_slot], which receives the parsed
+    /// contents of the given hints key.
+    Hints(String, oneshot::Sender<Result<Vec<Hint>, Error>>),
+
+    /// A request from [Client.my_hinted_unchecked_receipts], which receives
+    /// the hints for the local player's slot filtered down to unchecked
+    /// progression items that player will receive.
+    HintedReceipts(String, oneshot::Sender<Result<Vec<Hint>, Error>>),
+
+    /// The request for [RACE_MODE_KEY] that the client sends when it first
+    /// connects.
+    RaceMode,
+}
+
+/// The special data storage key that indicates whether the room is in race
+/// mode.
+const RACE_MODE_KEY: &str = "_read_race_mode";
+
 /// The client that talks to the Archipelago server using the Archipelago
 /// protocol.
 ///
@@ -33,12 +86,20 @@ const VERSION: NetworkVersion = NetworkVersion {
 /// JSON blob. If `S = ()`, this will not request slot data from the server at
 /// all.
 pub struct Client<S: DeserializeOwned + 'static = serde_json::Value> {
-    socket: Socket<S>,
+    socket: Socket,
 
     // == Session information
+    url: String,
+
+    /// The game being played by the local slot, pointing into [games].
+    ///
+    /// A slot's game is only ever sent in `Connected.slot_info`. `RoomUpdate`
+    /// can't change it, since its player entries only carry names and aliases,
+    /// so this only needs to be re-derived when [games] itself is modified.
     game: *const Game,
     server_version: Version,
     generator_version: Version,
+    compatibility_warnings: Vec<String>,
     server_tags: UstrSet,
     password_required: bool,
     permissions: PermissionMap,
@@ -46,6 +107,16 @@ pub struct Client<S: DeserializeOwned + 'static = serde_json::Value> {
     hint_points_per_check: u64,
     hint_points: i64,
     seed_name: String,
+
+    /// The names of all games in the room, as reported by `RoomInfo`.
+    room_games: UstrSet,
+
+    /// Whether the room is in race mode. This is `None` until the server
+    /// responds to the request we send for it when connecting.
+    race_mode: Option<bool>,
+
+    /// Whether [Event::CatchUpComplete] has been emitted yet.
+    caught_up: bool,
     games: UstrMap<Game>,
     slot_data: S,
 
@@ -57,23 +128,104 @@ pub struct Client<S: DeserializeOwned + 'static = serde_json::Value> {
     /// A map from `(team, slot)` to the player with that team and slot.
     players: HashMap<(u32, u32), Arc<Player>>,
 
+    /// The slot info from the server's `Connected` message, used to hydrate
+    /// players that only appear in later room updates.
+    slot_info: HashMap<u32, NetworkSlot>,
+
     /// The key for the current player in [players].
     player_key: (u32, u32),
 
-    /// A map from location IDs for this game to booleans indicating whether or
-    /// not they've been checked.
-    local_locations_checked: HashMap<i64, bool>,
+    /// All client tags that have been observed on players in the room, from
+    /// `Join` and `TagsChanged` messages.
+    player_tags: UstrSet,
+
+    /// The IDs of locations in this game that have been checked.
+    local_checked_locations: HashSet<i64>,
+
+    /// The IDs of locations in this game that haven't yet been checked. This
+    /// is always disjoint with [local_checked_locations].
+    local_unchecked_locations: HashSet<i64>,
+
+    /// The IDs of locations passed to
+    /// [mark_checked_unchecked](Self::mark_checked_unchecked) that aren't in
+    /// this game's data package. These are kept separate so the other location
+    /// sets only ever contain IDs that can be resolved to [Location]s.
+    unverified_checked_locations: HashSet<i64>,
 
     /// A list of all the items this client has ever received. This is
     /// overwritten if the server sends a new [ServerMessage::ReceivedItems]
     /// with index 0.
     received_items: Vec<ReceivedItem>,
 
+    /// The index just past the last item in the most recent
+    /// [ServerMessage::ReceivedItems], or `None` if none has been received.
+    last_received_index: Option<usize>,
+
+    /// The items that have been revealed by [Client.scout_locations], indexed
+    /// by the slot whose world contains them and their location ID.
+    scouted_items: HashMap<(u32, i64), LocatedItem>,
+
+    /// The maximum number of messages to process in a single call to
+    /// [update](Self::update), if any.
+    max_messages_per_update: Option<usize>,
+
+    /// If set, generic bounces are only emitted as events if they have at
+    /// least one of these tags.
+    bounce_filter: Option<UstrSet>,
+
+    /// The window within which incoming death links after the first are
+    /// suppressed, if any.
+    death_link_dedup: Option<Duration>,
+
+    /// The number of items in the initial [ServerMessage::ReceivedItems] that
+    /// shouldn't be reported as new. See
+    /// [ConnectionOptions::skip_initial_item_replay].
+    skip_items_before: Option<usize>,
+
+    /// The local time at which the last death link event was emitted.
+    last_death_link: Option<Instant>,
+
+    /// Events that have been generated by handling a server message but not yet
+    /// returned to the caller. This is used when a single message produces
+    /// more than one event.
+    pending_events: VecDeque<Event>,
+
     /// Senders for [Client.scout_locations].
     location_scout_senders: VecDeque<oneshot::Sender<Result<Vec<LocatedItem>, Error>>>,
 
-    /// Senders for [Client.get].
-    get_senders: VecDeque<oneshot::Sender<Result<HashMap<String, serde_json::Value>, Error>>>,
+    /// Senders for [Client.get] and other requests that use `Get` under the
+    /// hood.
+    get_senders: VecDeque<GetSender>,
+
+    /// The number of [Client.refresh_data_package] requests that haven't yet
+    /// been responded to.
+    data_package_requests: usize,
+
+    /// The data package checksums the server advertised in `RoomInfo`, used to
+    /// verify packages downloaded by [Client.refresh_data_package].
+    datapackage_checksums: UstrMap<String>,
+
+    /// The cache in which to store packages downloaded by
+    /// [Client.refresh_data_package], or `None` if caching is disabled.
+    cache: Option<Cache>,
+
+    /// Writes to [cache] that haven't finished yet. These are polled each time
+    /// the client is updated, since [update] can't block on them.
+    cache_writes: Vec<Pin<Box<dyn Future<Output = ()> + Send>>>,
+
+    /// The number of [Client.sync] requests that haven't yet been responded
+    /// to.
+    sync_requests: usize,
+
+    /// Whether to log mutating requests rather than sending them. See
+    /// [ConnectionOptions::dry_run].
+    dry_run: bool,
+
+    /// Callbacks registered with [Client.on_event].
+    event_callbacks: Vec<EventCallback>,
+
+    /// The items this client has asked the server to send.
+    item_handling: ItemHandling,
 }
 
 impl<S: DeserializeOwned + 'static> Client<S> {
@@ -83,10 +235,11 @@ impl<S: DeserializeOwned + 'static> Client<S> {
     /// followed by `ws://`. If it doesn't have a port, it defaults to the
     /// Archipelago default port 38281.
     ///
-    /// If `game` is `None`, [ConnectionOptions::tags] must contain at least one
-    /// of [tags::HINT_GAME], [tags::TRACKER] or [tags::TEXT_ONLY]. In this
-    /// case, the server won't validate that the slot you connect to is playing
-    /// a particular game.
+    /// If `game` is `None` or empty, [ConnectionOptions::tags] must contain at
+    /// least one of [tags::HINT_GAME], [tags::TRACKER] or [tags::TEXT_ONLY]. In
+    /// this case, the server won't validate that the slot you connect to is
+    /// playing a particular game. The client still knows which game the slot is
+    /// playing, so [this_game](Self::this_game) works as usual.
     ///
     /// [tags::HINT_GAME]: crate::tags::HINT_GAME
     /// [tags::TRACKER]: crate::tags::TRACKER
@@ -100,19 +253,71 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         game: Option<impl Into<Ustr>>,
         options: ConnectionOptions,
     ) -> Result<Client<S>, Error> {
-        if game.is_none()
-            && !options.tags.contains(&"HintGame".into())
-            && !options.tags.contains(&"Tracker".into())
-            && !options.tags.contains(&"TextOnly".into())
-        {
-            return Err(ArgumentError::MissingGame { tags: options.tags }.into());
+        Self::connect_with_slot_data_decoder(url, name, game, options, deserialize_slot_data).await
+    }
+
+    /// Like [connect](Self::connect), but uses `decode_slot_data` to convert
+    /// the raw slot data into `S` rather than deserializing it directly.
+    ///
+    /// This is useful for apworlds that version their slot data, since it
+    /// allows the caller to try multiple schemas in whatever order they choose.
+    /// If `decode_slot_data` returns an error, the connection fails with that
+    /// error.
+    pub async fn connect_with_slot_data_decoder(
+        url: impl Into<String>,
+        name: impl Into<Ustr>,
+        game: Option<impl Into<Ustr>>,
+        options: ConnectionOptions,
+        decode_slot_data: impl FnOnce(&serde_json::Value) -> Result<S, Error>,
+    ) -> Result<Client<S>, Error> {
+        Self::connect_with_progress(
+            url,
+            name,
+            game,
+            options,
+            decode_slot_data,
+            &Default::default(),
+        )
+        .await
+    }
+
+    /// Like [connect_with_slot_data_decoder](Self::connect_with_slot_data_decoder),
+    /// but updates `progress` as the connection moves through each phase of
+    /// the handshake.
+    pub(crate) async fn connect_with_progress(
+        url: impl Into<String>,
+        name: impl Into<Ustr>,
+        game: Option<impl Into<Ustr>>,
+        options: ConnectionOptions,
+        decode_slot_data: impl FnOnce(&serde_json::Value) -> Result<S, Error>,
+        progress: &ConnectingProgress,
+    ) -> Result<Client<S>, Error> {
+        let game = game.map(|g| g.into());
+        let gameless = options.tags.contains(&"HintGame".into())
+            || options.tags.contains(&"Tracker".into())
+            || options.tags.contains(&"TextOnly".into());
+        match game {
+            None if !gameless => {
+                return Err(ArgumentError::MissingGame { tags: options.tags }.into());
+            }
+            Some(game) if game.is_empty() && !gameless => {
+                return Err(ArgumentError::EmptyGame.into());
+            }
+            _ => {}
         }
 
+        let max_messages_per_update = options.max_messages_per_update;
+        let bounce_filter = options.bounce_filter;
+        let death_link_dedup = options.death_link_dedup;
+        let skip_items_before = options.skip_items_before;
+        let dry_run = options.dry_run;
+        let item_handling = options.item_handling.clone();
         let url = url.into();
         let mut socket = if url.as_str().starts_with("ws://") || url.as_str().starts_with("wss://")
         {
             Socket::connect(
-                url,
+                url.clone(),
+                options.subprotocol.as_deref(),
                 #[cfg(feature = "rustls")]
                 options.rustls_config,
             )
@@ -120,6 +325,7 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         } else {
             match Socket::connect(
                 format!("wss://{}", url),
+                options.subprotocol.as_deref(),
                 #[cfg(feature = "rustls")]
                 options.rustls_config.clone(),
             )
@@ -129,6 +335,7 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                 Err(Error::WebSocket(err)) => {
                     match Socket::connect(
                         format!("ws://{}", url),
+                        options.subprotocol.as_deref(),
                         #[cfg(feature = "rustls")]
                         options.rustls_config,
                     )
@@ -143,45 +350,95 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         };
 
         log::debug!("Awaiting RoomInfo...");
+        progress.set(ConnectingPhase::AwaitingRoomInfo);
         let room_info = match socket.recv_async().await? {
             ServerMessage::RoomInfo(room_info) => room_info,
             message => return Err(Self::unexpected_response(message, "RoomInfo")),
         };
 
         log::debug!("Loading Cached DataPackages...");
-        let cache = options.cache.unwrap_or_default();
-        let mut packages = cache
-            .load_data_packages(&room_info.datapackage_checksums)
-            .await;
+        progress.set(ConnectingPhase::LoadingCachedDataPackages);
+        let cache = if options.no_cache {
+            None
+        } else {
+            Some(options.cache.unwrap_or_default())
+        };
+        let mut packages = match &cache {
+            Some(cache) => {
+                cache
+                    .load_data_packages(&room_info.datapackage_checksums)
+                    .await
+            }
+            None => Default::default(),
+        };
         // Determine which games we are missing by comparing the checksums
         // received with what we found in the cache.
         let missing = room_info
             .datapackage_checksums
             .keys()
             .filter(|k| !packages.contains_key(*k))
-            .map(|k| k.to_string())
+            .copied()
             .collect::<Vec<_>>();
         if !missing.is_empty() {
-            log::debug!("Awaiting DataPackage...");
-            socket.send(ClientMessage::GetDataPackage(GetDataPackage {
-                games: Some(missing),
-            }))?;
-            let received_dp = match socket.recv_async().await? {
-                ServerMessage::DataPackage(DataPackage { data }) => data,
-                message => return Err(Self::unexpected_response(message, "DataPackage")),
-            };
-            log::debug!("Writing new entries to cache...");
-            cache.store_data_packages(&received_dp.games).await;
-            packages.extend(received_dp.games);
+            log::debug!("Awaiting DataPackages...");
+            // Request each game separately so that we can report progress as
+            // they arrive and so that no single message is too large to parse
+            // quickly.
+            let mut received = UstrMap::<GameData>::default();
+            let mut unreceived = Vec::new();
+            for (index, game) in missing.iter().copied().enumerate() {
+                progress.set(ConnectingPhase::DataPackage {
+                    game,
+                    index,
+                    total: missing.len(),
+                });
+                socket.send(ClientMessage::GetDataPackage(GetDataPackage {
+                    games: Some(vec![game.to_string()]),
+                }))?;
+                let mut data = match socket.recv_async().await? {
+                    ServerMessage::DataPackage(DataPackage { data }) => data,
+                    message => return Err(Self::unexpected_response(message, "DataPackage")),
+                };
+
+                let Some(data) = data.games.remove(&game) else {
+                    unreceived.push(game);
+                    continue;
+                };
+                verify_checksum(game, &data, &room_info.datapackage_checksums)?;
+                received.insert(game, data);
+            }
+
+            if !unreceived.is_empty() {
+                // The session can still proceed without these. Their games will
+                // be treated as having no data package, so their items and
+                // locations won't have names.
+                log::warn!(
+                    "Server didn't send data packages for {}",
+                    unreceived
+                        .iter()
+                        .map(|g| g.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+
+            if let Some(cache) = &cache {
+                log::debug!("Writing new entries to cache...");
+                cache.store_data_packages(&received).await;
+            }
+            packages.extend(received);
         }
         let data_package = DataPackageObject { games: packages };
 
         log::debug!("Awaiting Connected...");
+        progress.set(ConnectingPhase::AwaitingConnected);
         let mut version = VERSION.clone();
         version.class = "Version".into();
         socket.send(ClientMessage::Connect(Connect {
             password: options.password,
-            game: game.map(|g| g.into()),
+            // An empty game is only allowed for non-playing clients, which the
+            // server expects to send no game at all.
+            game: game.filter(|g| !g.is_empty()),
             name: name.into(),
             // Specify something useful here if
             // ArchipelagoMW/Archipelago#998 ever gets sorted out.
@@ -192,6 +449,10 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             slot_data: !try_specialize::static_type_eq::<S, ()>(),
         }))?;
 
+        // The server often sends ReceivedItems and other messages in the same
+        // frame as Connected. [Socket::recv_async] only pops one message, so
+        // the rest stay buffered in the socket, which moves into the client
+        // and handles them on the first update.
         let connected = match socket.recv_async().await? {
             ServerMessage::Connected(connected) => connected,
             ServerMessage::ConnectionRefused(ConnectionRefused { errors }) => {
@@ -202,27 +463,79 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             message => return Err(Self::unexpected_response(message, "Connected")),
         };
 
-        let client = Client::new(socket, room_info, data_package, connected)?;
+        let slot_data = decode_slot_data(&connected.slot_data)?;
+        let mut client = Client::new(
+            socket,
+            room_info,
+            data_package,
+            connected,
+            slot_data,
+            gameless,
+        )?;
+        client.max_messages_per_update = max_messages_per_update;
+        client.bounce_filter = bounce_filter;
+        client.death_link_dedup = death_link_dedup;
+        client.skip_items_before = skip_items_before;
+        client.dry_run = dry_run;
+        client.item_handling = item_handling;
+        client.url = url;
+        client.cache = cache;
+        // Because the server handles messages in order, the response to this
+        // also marks the point at which it's finished sending the state the
+        // client missed while disconnected.
+        client.socket.send(ClientMessage::Get(Get {
+            keys: vec![RACE_MODE_KEY.into()],
+        }))?;
+        client.get_senders.push_back(GetSender::RaceMode);
         log::info!("Archipelago connection initialized successfully");
         Ok(client)
     }
 
     /// Creates a new client with all available initial information.
+    ///
+    /// `gameless` indicates that the connection's tags allow it to connect
+    /// without playing a game.
     fn new(
-        socket: Socket<S>,
+        socket: Socket,
         room_info: RoomInfo,
         data_package: DataPackageObject,
-        connected: Connected<S>,
+        connected: Connected,
+        slot_data: S,
+        gameless: bool,
     ) -> Result<Self, Error> {
         let server_skew = SignedDuration::difference(SystemTime::now(), room_info.time);
-        let total_locations = connected.checked_locations.len() + connected.missing_locations.len();
+
+        let server_version = Version::from(room_info.version);
+        let generator_version = Version::from(room_info.generator_version);
+        let mut compatibility_warnings = Vec::new();
+        if server_version > MAX_SERVER_VERSION {
+            compatibility_warnings.push(format!(
+                "This server is running Archipelago {server_version}, which is newer than this \
+                 client supports."
+            ));
+        }
+        if generator_version < MIN_GENERATOR_VERSION {
+            compatibility_warnings.push(format!(
+                "This multiworld was generated by Archipelago {generator_version}, which is \
+                 older than the oldest supported version ({MIN_GENERATOR_VERSION})."
+            ));
+        } else if generator_version > MAX_GENERATOR_VERSION {
+            compatibility_warnings.push(format!(
+                "This multiworld was generated by Archipelago {generator_version}, which is \
+                 newer than this client supports."
+            ));
+        }
+        let mut pending_events = VecDeque::with_capacity(compatibility_warnings.len());
+        for warning in &compatibility_warnings {
+            log::warn!("{warning}");
+            pending_events.push_back(Event::CompatibilityWarning(warning.clone()));
+        }
 
         let teams = connected
             .players
             .iter()
             .map(|p| p.team)
-            .max()
-            .ok_or(ProtocolError::EmptyPlayers)?
+            .fold(connected.team, u32::max)
             + 1;
 
         let mut players = HashMap::<(u32, u32), Arc<Player>>::new();
@@ -241,13 +554,29 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             players.insert((team, 0), Player::archipelago(team).into());
         }
 
+        // Connections that aren't playing a game (text-only, tracker, and
+        // hint-game clients as well as spectators) may not appear in the
+        // players list. As long as the server provided slot info for the
+        // connecting slot, we can construct a player for it anyway. A missing
+        // player for any other connection indicates a server bug.
         let player_key = (connected.team, connected.slot);
         if !players.contains_key(&player_key) {
-            return Err(ProtocolError::MissingPlayer {
+            let slot_info = connected
+                .slot_info
+                .get(&connected.slot)
+                .filter(|info| gameless || info.r#type == SlotType::Spectator)
+                .ok_or(ProtocolError::MissingPlayer {
+                    team: connected.team,
+                    slot: connected.slot,
+                })?;
+            let network = NetworkPlayer {
                 team: connected.team,
                 slot: connected.slot,
-            }
-            .into());
+                alias: slot_info.name.to_string(),
+                name: slot_info.name,
+            };
+            let player = Player::hydrate(network, slot_info, &players)?;
+            players.insert(player_key, player.into());
         }
 
         let mut games = data_package
@@ -255,7 +584,7 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             .into_iter()
             .map(|(name, data)| (name, Game::hydrate(name, data)))
             .collect::<UstrMap<_>>();
-        for game_name in room_info.games {
+        for game_name in room_info.games.iter().copied() {
             games
                 .entry(game_name)
                 .or_insert_with(|| Game::no_data_package(game_name));
@@ -271,21 +600,26 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             .ok_or(ProtocolError::MissingGameData(game))?;
         let game_ptr = ptr::from_ref(game);
 
-        let mut local_locations_checked = HashMap::with_capacity(total_locations);
+        let mut local_unchecked_locations =
+            HashSet::with_capacity(connected.missing_locations.len());
         for id in connected.missing_locations {
             game.verify_location(id)?;
-            local_locations_checked.insert(id, false);
+            local_unchecked_locations.insert(id);
         }
+        let mut local_checked_locations = HashSet::with_capacity(connected.checked_locations.len());
         for id in connected.checked_locations {
             game.verify_location(id)?;
-            local_locations_checked.insert(id, true);
+            local_unchecked_locations.remove(&id);
+            local_checked_locations.insert(id);
         }
 
         Ok(Client {
             socket,
+            url: Default::default(),
             game: game_ptr,
-            server_version: room_info.version.into(),
-            generator_version: room_info.generator_version.into(),
+            server_version,
+            generator_version,
+            compatibility_warnings,
             server_tags: room_info.tags,
             password_required: room_info.password_required,
             permissions: room_info.permissions,
@@ -293,20 +627,43 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             hint_points_per_check: room_info.location_check_points,
             hint_points: connected.hint_points,
             seed_name: room_info.seed_name,
+            room_games: room_info.games,
+            race_mode: None,
+            caught_up: false,
             games,
-            slot_data: connected.slot_data,
+            slot_data,
             server_skew,
             players,
+            slot_info: connected.slot_info,
             player_key,
-            local_locations_checked,
+            player_tags: Default::default(),
+            local_checked_locations,
+            local_unchecked_locations,
+            unverified_checked_locations: Default::default(),
             received_items: Default::default(),
+            last_received_index: None,
+            scouted_items: Default::default(),
+            max_messages_per_update: None,
+            bounce_filter: None,
+            death_link_dedup: None,
+            skip_items_before: None,
+            last_death_link: None,
+            pending_events,
             location_scout_senders: Default::default(),
             get_senders: Default::default(),
+            data_package_requests: 0,
+            datapackage_checksums: room_info.datapackage_checksums,
+            cache: None,
+            cache_writes: Default::default(),
+            sync_requests: 0,
+            dry_run: false,
+            event_callbacks: Default::default(),
+            item_handling: Default::default(),
         })
     }
 
     /// Returns an error indicating that [message] is unexpected.
-    fn unexpected_response(message: ServerMessage<S>, expected: &'static str) -> Error {
+    fn unexpected_response(message: ServerMessage, expected: &'static str) -> Error {
         if let ServerMessage::InvalidPacket(invalid) = message {
             Error::InvalidPacket(invalid.text)
         } else {
@@ -327,6 +684,20 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         unsafe { &*self.game }
     }
 
+    /// The transport this client is using to communicate with the server.
+    ///
+    /// This indicates whether the connection ended up using TLS, which may not
+    /// be the case even for URLs without an explicit protocol if the server
+    /// doesn't support it.
+    pub fn transport(&self) -> Transport {
+        self.socket.transport()
+    }
+
+    /// The URL that was used to connect to the server.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
     /// The version of Archipelago which the server is running.
     pub fn server_version(&self) -> &Version {
         &self.server_version
@@ -337,11 +708,32 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         &self.generator_version
     }
 
+    /// Human-readable warnings about ways this multiworld may not be fully
+    /// compatible with this client, such as having been generated by a version
+    /// of Archipelago this client doesn't know about.
+    ///
+    /// These are also logged and emitted as [Event::CompatibilityWarning]s
+    /// when the client connects. Clients may want to show them to the player,
+    /// since they indicate that the game may behave unexpectedly.
+    pub fn compatibility_warnings(&self) -> &[String] {
+        &self.compatibility_warnings
+    }
+
     /// The server's special features or capabilities.
     pub fn server_tags(&self) -> &UstrSet {
         &self.server_tags
     }
 
+    /// All tags that are known to be in use in this room.
+    ///
+    /// This includes the [server tags](Self::server_tags) as well as all client
+    /// tags that players have been observed using since this client connected.
+    /// Tags are never removed from this list, even if the players who used
+    /// them disconnect.
+    pub fn known_tags(&self) -> impl UnsizedIter<Ustr> {
+        self.server_tags.union(&self.player_tags).copied()
+    }
+
     /// Whether this Archipelago multiworld requires a password to join.
     pub fn password_required(&self) -> bool {
         self.password_required
@@ -367,7 +759,15 @@ impl<S: DeserializeOwned + 'static> Client<S> {
     /// The number of hint points the player must accumulate in order to access
     /// a single hint.
     pub fn points_per_hint(&self) -> u64 {
-        (self.local_locations_checked.len() as u64) * u64::from(self.hint_cost_percentage) / 100
+        if self.hint_cost_percentage == 0 {
+            return 0;
+        }
+
+        // Like the server, charge at least one point per hint unless hints are
+        // explicitly free, even for worlds with very few locations.
+        let total_locations =
+            self.local_checked_locations.len() + self.local_unchecked_locations.len();
+        ((total_locations as u64) * u64::from(self.hint_cost_percentage) / 100).max(1)
     }
 
     /// The number of hint points granted for each location a player checks.
@@ -384,6 +784,18 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         self.hint_points
     }
 
+    /// The number of hints the player can currently afford, based on
+    /// [hint_points](Self::hint_points) and
+    /// [points_per_hint](Self::points_per_hint).
+    ///
+    /// If hints are free, this returns [u64::MAX].
+    pub fn affordable_hints(&self) -> u64 {
+        match self.points_per_hint() {
+            0 => u64::MAX,
+            cost => u64::try_from(self.hint_points).unwrap_or(0) / cost,
+        }
+    }
+
     /// The uniquely-identifying name of the generated multiworld.
     ///
     /// If the same multiworld is hosted in multiple rooms, this will be the
@@ -392,12 +804,98 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         self.seed_name.as_str()
     }
 
+    /// Whether this room is running in race mode, which restricts access to
+    /// spoilers. Clients should disable spoiler features in race mode.
+    ///
+    /// The client requests this from the server when it first connects, so
+    /// this returns `None` until the server responds.
+    pub fn race_mode(&self) -> Option<bool> {
+        self.race_mode
+    }
+
+    /// Like [race_mode](Self::race_mode), but returns `false` if the server
+    /// hasn't responded yet.
+    ///
+    /// The server answers before [Event::CatchUpComplete] is emitted, so this
+    /// is accurate from then on.
+    pub fn is_race_mode(&self) -> bool {
+        self.race_mode.unwrap_or(false)
+    }
+
     /// A map from the names of each game in this multiworld to metadata about
     /// those games.
     pub fn games(&self) -> impl Iter<&Game> {
         self.games.values()
     }
 
+    /// The names of every game being played in this room, as reported by the
+    /// server when the client connected.
+    ///
+    /// Unlike [games](Self::games), this doesn't include games that only
+    /// appear in the data package (such as [Game::archipelago]). Games in this
+    /// list that the server didn't send a data package for still appear in
+    /// [games](Self::games), but without item or location names.
+    pub fn room_games(&self) -> impl Iter<Ustr> {
+        self.room_games.iter().copied()
+    }
+
+    /// The total number of locations defined across all games in this
+    /// multiworld.
+    ///
+    /// Each game's locations are counted once, regardless of how many players
+    /// are playing it. This doesn't include the special [Game::archipelago]
+    /// game or games without data packages.
+    pub fn multiworld_location_count(&self) -> usize {
+        self.games()
+            .filter(|g| g.name() != *ARCHIPELAGO_NAME)
+            .map(|g| g.locations().len())
+            .sum()
+    }
+
+    /// The total number of items defined across all games in this multiworld.
+    ///
+    /// Each game's items are counted once, regardless of how many players are
+    /// playing it. This doesn't include the special [Game::archipelago] game or
+    /// games without data packages.
+    pub fn multiworld_item_count(&self) -> usize {
+        self.games()
+            .filter(|g| g.name() != *ARCHIPELAGO_NAME)
+            .map(|g| g.items().len())
+            .sum()
+    }
+
+    /// Returns each game in this multiworld along with the number of players
+    /// on the current team who are playing it.
+    ///
+    /// This doesn't include the special [Game::archipelago] game, and it
+    /// doesn't count item link groups as players.
+    pub fn games_with_player_counts(&self) -> impl UnsizedIter<(&Game, usize)> {
+        let mut counts = UstrMap::<usize>::default();
+        for player in self.players() {
+            if player.team() == self.player_key.0
+                && player.slot() != 0
+                && player.group_members().is_empty()
+            {
+                *counts.entry(player.game()).or_default() += 1;
+            }
+        }
+
+        self.games()
+            .filter(|g| g.name() != *ARCHIPELAGO_NAME)
+            .map(move |g| (g, counts.get(&g.name()).copied().unwrap_or(0)))
+    }
+
+    /// The item groups defined by the connected game. See [Game::item_groups].
+    pub fn my_item_groups(&self) -> impl Iter<(Ustr, &[Ustr])> {
+        self.this_game().item_groups()
+    }
+
+    /// The location groups defined by the connected game. See
+    /// [Game::location_groups].
+    pub fn my_location_groups(&self) -> impl Iter<(Ustr, &[Ustr])> {
+        self.this_game().location_groups()
+    }
+
     /// Returns the game with the given `name`, if one is in this multiworld.
     ///
     /// Unlike [games](Self::games), this will return the special
@@ -436,11 +934,43 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         self.players[&self.player_key].as_ref()
     }
 
+    /// The team number of the player that's currently connected to the
+    /// multiworld.
+    pub fn team(&self) -> u32 {
+        self.player_key.0
+    }
+
+    /// The slot number of the player that's currently connected to the
+    /// multiworld.
+    pub fn slot(&self) -> u32 {
+        self.player_key.1
+    }
+
+    /// Whether the connected slot is a spectator slot. Spectators can't
+    /// receive items or check locations.
+    pub fn is_spectator(&self) -> bool {
+        self.slot_info
+            .get(&self.player_key.1)
+            .is_some_and(|info| info.r#type == SlotType::Spectator)
+    }
+
     /// All players in the multiworld.
     pub fn players(&self) -> impl Iter<&Player> {
         self.players.values().map(|p| p.as_ref())
     }
 
+    /// All item link groups that the current player is a member of.
+    ///
+    /// Groups are represented as [Player]s whose
+    /// [group_members](Player::group_members) aren't empty.
+    pub fn my_item_links(&self) -> impl UnsizedIter<&Player> {
+        let this_player = self.this_player();
+        self.players
+            .values()
+            .map(|p| p.as_ref())
+            .filter(move |p| p.group_members().iter().any(|m| **m == *this_player))
+    }
+
     /// The player on the given `team` playing the given `slot`, if one exists.
     ///
     /// See also [teammate](Self::teammate) to only check the current player's team.
@@ -472,12 +1002,56 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         })
     }
 
+    /// The player whose original slot name is `name`, if one exists.
+    ///
+    /// If multiple teams have players with this name, this prefers the one on
+    /// the current player's team.
+    pub fn player_by_name(&self, name: impl Into<Ustr>) -> Option<&Player> {
+        let name = name.into();
+        self.find_player(|p| p.name() == name)
+    }
+
+    /// The player whose current alias is `alias`, if one exists.
+    ///
+    /// If multiple players have this alias, this prefers one on the current
+    /// player's team.
+    pub fn player_by_alias(&self, alias: impl AsRef<str>) -> Option<&Player> {
+        let alias = alias.as_ref();
+        self.find_player(|p| p.alias() == alias)
+    }
+
+    /// Returns a player that matches `predicate`, preferring players on the
+    /// current player's team.
+    fn find_player(&self, predicate: impl Fn(&Player) -> bool) -> Option<&Player> {
+        let mut fallback = None;
+        for player in self.players() {
+            if predicate(player) {
+                if player.team() == self.player_key.0 {
+                    return Some(player);
+                }
+                fallback.get_or_insert(player);
+            }
+        }
+        fallback
+    }
+
     /// The player playing the given `slot` on the current player's team, if one
     /// exists.
     pub fn teammate(&self, slot: u32) -> Option<&Player> {
         self.player(self.player_key.0, slot)
     }
 
+    /// The name of the game played by the given `slot` on the current player's
+    /// team, if the slot exists.
+    ///
+    /// This works for item link group slots as well as individual players. For
+    /// slot 0, it returns [Game::archipelago]'s name.
+    pub fn game_for_slot(&self, slot: u32) -> Option<Ustr> {
+        self.teammate(slot)
+            .map(|p| p.game())
+            .or_else(|| self.slot_info.get(&slot).map(|info| info.game))
+    }
+
     /// A clone of the [Arc] for the player playing the given [slot] on the
     /// current player's team.
     pub(crate) fn teammate_arc(&self, slot: u32) -> Result<Arc<Player>, Error> {
@@ -498,7 +1072,7 @@ impl<S: DeserializeOwned + 'static> Client<S> {
     /// world.
     pub fn is_local_location_checked(&self, id: impl AsLocationId) -> bool {
         let id = id.as_location_id();
-        *self.local_locations_checked.get(&id).unwrap_or_else(|| {
+        self.try_is_local_location_checked(id).unwrap_or_else(|| {
             panic!(
                 "Archipelago location ID {} doesn't exist for {}",
                 id,
@@ -507,22 +1081,42 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         })
     }
 
+    /// Like [is_local_location_checked](Self::is_local_location_checked), but
+    /// returns `None` rather than panicking if the location ID doesn't exist
+    /// for this world.
+    pub fn try_is_local_location_checked(&self, id: impl AsLocationId) -> Option<bool> {
+        let id = id.as_location_id();
+        if self.local_checked_locations.contains(&id) {
+            Some(true)
+        } else if self.local_unchecked_locations.contains(&id) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether every location in the connected game's world has been
+    /// checked (either by us or by other players doing co-op).
+    ///
+    /// This returns `true` for worlds that have no locations at all.
+    pub fn all_local_locations_checked(&self) -> bool {
+        self.local_unchecked_locations.is_empty()
+    }
+
     /// Returns all the locations that the player has already checked.
-    pub fn checked_locations(&self) -> impl UnsizedIter<Location> {
+    pub fn checked_locations(&self) -> impl Iter<Location> {
         let game = self.this_game();
-        self.local_locations_checked
+        self.local_checked_locations
             .iter()
-            .filter(|(_, checked)| **checked)
-            .map(|(id, _)| game.assert_location(*id))
+            .map(|id| game.assert_location(*id))
     }
 
     /// Returns all the locations that the player has not yet checked.
-    pub fn unchecked_locations(&self) -> impl UnsizedIter<Location> {
+    pub fn unchecked_locations(&self) -> impl Iter<Location> {
         let game = self.this_game();
-        self.local_locations_checked
+        self.local_unchecked_locations
             .iter()
-            .filter(|(_, checked)| !**checked)
-            .map(|(id, _)| game.assert_location(*id))
+            .map(|id| game.assert_location(*id))
     }
 
     /// Returns all items that have ever been received by this client. Note that
@@ -531,6 +1125,98 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         &self.received_items
     }
 
+    /// Returns the number of items that have ever been received by this
+    /// client, including items the player found in their own world.
+    ///
+    /// Like [received_items](Self::received_items), this is 0 until the initial
+    /// [Event::ReceivedItems] is emitted. To track how many of this world's
+    /// items have been sent to other players, compare
+    /// [checked_locations](Self::checked_locations) against the results of
+    /// [scout_locations](Self::scout_locations), since the server doesn't
+    /// report that information unless it's scouted.
+    pub fn items_received_count(&self) -> usize {
+        self.received_items.len()
+    }
+
+    /// Returns the index just past the last item in the most recent batch of
+    /// received items, or `None` if no items have been received yet.
+    ///
+    /// This is the value the Archipelago protocol recommends storing in the
+    /// player's save file. When reconnecting, if the server's first
+    /// [Event::ReceivedItems] doesn't line up with the stored value, the client
+    /// can tell that it missed items while it was disconnected.
+    pub fn last_received_index(&self) -> Option<usize> {
+        self.last_received_index
+    }
+
+    /// Returns the item at `location` in `slot`'s world, if it's been revealed
+    /// by a previous call to [scout_locations](Self::scout_locations).
+    ///
+    /// Scout results are cached until [clear_scout_cache](Self::clear_scout_cache)
+    /// is called or the client's data packages are refreshed, so this never
+    /// requires another round trip to the server.
+    pub fn scouted_item(&self, slot: u32, location: impl AsLocationId) -> Option<&LocatedItem> {
+        self.scouted_items.get(&(slot, location.as_location_id()))
+    }
+
+    /// Discards all the items cached by
+    /// [scout_locations](Self::scout_locations), so that
+    /// [scouted_item](Self::scouted_item) returns `None` until the locations
+    /// are scouted again.
+    pub fn clear_scout_cache(&mut self) {
+        self.scouted_items.clear();
+    }
+
+    /// Summarizes the results of [scout_locations](Self::scout_locations),
+    /// grouping `items` by the player who will receive them and counting how
+    /// many are progression, useful, or traps.
+    pub fn scout_summary(&self, items: &[LocatedItem]) -> ScoutSummary {
+        ScoutSummary::new(items)
+    }
+
+    /// Converts a raw [NetworkItem] into a [LocatedItem].
+    ///
+    /// This is useful for clients that pass items to one another through
+    /// custom protocols, such as [bounce](Self::bounce) payloads. The
+    /// `network` item's [player](NetworkItem::player) is treated as the slot
+    /// in whose world the item was found, and `receiver` is the slot that will
+    /// receive it. Both are on the local player's team.
+    ///
+    /// The protocol uses [NetworkItem::player] for the sender in some messages
+    /// and the receiver in others, so a [NetworkItem] on its own doesn't
+    /// identify both players. That's why the receiver must be passed
+    /// separately.
+    pub fn hydrate_item(&self, network: NetworkItem, receiver: u32) -> Result<LocatedItem, Error> {
+        let sender = self.teammate_arc(network.player)?;
+        let receiver = self.teammate_arc(receiver)?;
+        LocatedItem::hydrate(network, sender, receiver, self)
+    }
+
+    /// Returns the number of requests that are still waiting for a response
+    /// from the server, such as [scout_locations](Self::scout_locations) and
+    /// [get](Self::get).
+    ///
+    /// This only counts requests made through the client's public API, not
+    /// requests the client makes internally (such as the one it uses to check
+    /// [race_mode](Self::race_mode) on connect). This is mostly useful for
+    /// diagnosing requests that never seem to resolve.
+    pub fn pending_request_count(&self) -> usize {
+        let internal = self
+            .get_senders
+            .iter()
+            .filter(|sender| matches!(sender, GetSender::RaceMode))
+            .count();
+        self.location_scout_senders.len() + self.get_senders.len() - internal
+            + self.data_package_requests
+    }
+
+    /// Returns which items this client is receiving from the server, as set by
+    /// [ConnectionOptions::receive_items] or
+    /// [update_connection](Self::update_connection).
+    pub fn items_handling(&self) -> &ItemHandling {
+        &self.item_handling
+    }
+
     /// Returns the slot data provided by the apworld.
     pub fn slot_data(&self) -> &S {
         &self.slot_data
@@ -540,22 +1226,61 @@ impl<S: DeserializeOwned + 'static> Client<S> {
 
     /// Updates the current connection settings with new `item_handling` and/or
     /// `tags`.
+    ///
+    /// Spectators can't receive items, so this returns
+    /// [ArgumentError::Spectator] if `item_handling` would receive any on a
+    /// spectator connection.
     pub fn update_connection(
         &mut self,
         item_handling: Option<ItemHandling>,
         tags: Option<impl IntoIterator<Item: Into<Ustr>>>,
     ) -> Result<(), Error> {
+        if self.is_spectator() && matches!(item_handling, Some(ItemHandling::OtherWorlds { .. })) {
+            return Err(ArgumentError::Spectator.into());
+        }
+
         self.socket
             .send(ClientMessage::ConnectUpdate(ConnectUpdate {
-                items_handling: item_handling.map(|i| i.into()),
+                items_handling: item_handling.clone().map(|i| i.into()),
                 tags: tags.map(|ts| ts.into_iter().map(|t| t.into()).collect()),
-            }))
+            }))?;
+        if let Some(item_handling) = item_handling {
+            self.item_handling = item_handling;
+        }
+        Ok(())
     }
 
     /// Requests that the server resends all items this client has ever
     /// received. This will appear as an [Event::ReceivedItems] with index 0.
+    ///
+    /// Spectators can't receive items, so this returns
+    /// [ArgumentError::Spectator] on a spectator connection.
     pub fn sync(&mut self) -> Result<(), Error> {
-        self.socket.send(ClientMessage::Sync)
+        if self.is_spectator() {
+            return Err(ArgumentError::Spectator.into());
+        }
+
+        self.socket.send(ClientMessage::Sync)?;
+        self.sync_requests += 1;
+        Ok(())
+    }
+
+    /// Requests fresh data packages from the server for `games`, or for every
+    /// game in the multiworld if `games` is `None`, bypassing the cache.
+    ///
+    /// Once the server responds, the client verifies the new data against the
+    /// checksums the server advertised when the client connected, writes it to
+    /// the [Cache], replaces its [Game]s with it, and emits [Event::Updated]
+    /// with [UpdatedField::GameData]. If the checksums don't match, it emits
+    /// [Event::Error] with [ProtocolError::ChecksumMismatch] instead. This is
+    /// mostly useful for debugging stale cache entries, since the client
+    /// already refreshes data packages whose checksums have changed when it
+    /// connects.
+    pub fn refresh_data_package(&mut self, games: Option<Vec<String>>) -> Result<(), Error> {
+        self.socket
+            .send(ClientMessage::GetDataPackage(GetDataPackage { games }))?;
+        self.data_package_requests += 1;
+        Ok(())
     }
 
     /// Notifies the server that the given `locations` have been checked.
@@ -564,18 +1289,63 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         locations: impl IntoIterator<Item = impl AsLocationId>,
     ) -> Result<(), Error> {
         let locations = self.verify_local_locations(locations)?;
-        self.socket
-            .send(ClientMessage::LocationChecks(LocationChecks {
-                locations: locations.clone(),
-            }))?;
+        self.mark_checked_ids(locations)
+    }
 
+    /// Like [mark_checked](Self::mark_checked), but doesn't verify that
+    /// `locations` exist in the local game's data package.
+    ///
+    /// This is intended for games that reveal locations dynamically, where a
+    /// valid location may not (yet) appear in the data package the client
+    /// received. The server still validates the locations, but it silently
+    /// ignores any it doesn't recognize, so passing an invalid ID here won't
+    /// produce an error.
+    ///
+    /// Locations that aren't in the data package aren't included in
+    /// [checked_locations](Self::checked_locations) or in the hint point
+    /// calculations.
+    pub fn mark_checked_unchecked(
+        &mut self,
+        locations: impl IntoIterator<Item = impl AsLocationId>,
+    ) -> Result<(), Error> {
+        let locations = locations
+            .into_iter()
+            .map(|l| l.as_location_id())
+            .collect::<Vec<_>>();
+        self.mark_checked_ids(locations)
+    }
+
+    /// Notifies the server that the location IDs in `locations` have been
+    /// checked and records them locally, without verifying them.
+    ///
+    /// Any IDs that aren't already known local locations are recorded in
+    /// [unverified_checked_locations](Self::unverified_checked_locations).
+    fn mark_checked_ids(&mut self, locations: Vec<i64>) -> Result<(), Error> {
+        if self.is_spectator() {
+            return Err(ArgumentError::Spectator.into());
+        }
+
+        self.send_mutation(ClientMessage::LocationChecks(LocationChecks {
+            locations: locations.clone(),
+        }))?;
+
+        let old_hint_points = self.hint_points;
         for id in locations {
-            if matches!(self.local_locations_checked.insert(id, true), Some(false)) {
+            if self.local_unchecked_locations.remove(&id) {
+                self.local_checked_locations.insert(id);
                 self.hint_points = self
                     .hint_points
                     .strict_add_unsigned(self.hint_points_per_check);
+            } else if !self.local_checked_locations.contains(&id) {
+                self.unverified_checked_locations.insert(id);
             }
         }
+        if self.hint_points != old_hint_points {
+            self.pending_events.push_back(Event::HintPointsChanged {
+                old: old_hint_points,
+                new: self.hint_points,
+            });
+        }
         Ok(())
     }
 
@@ -590,6 +1360,17 @@ impl<S: DeserializeOwned + 'static> Client<S> {
     ///   checked. If [CreateAsHint.All] or [CreateAsHint.New] is passed,
     ///   scouted locations will be broadcast as hints without deducting hint
     ///   points from the player.
+    ///
+    /// The returned receiver always resolves with every scouted location,
+    /// regardless of `create_as_hint`. Any hints created by the scout are
+    /// emitted separately as [Print::Hint] events, just like hints created any
+    /// other way. If the server rejects the request, the receiver resolves with
+    /// [Error::InvalidPacket].
+    ///
+    /// It's safe to drop the receiver to ignore the result. Responses are
+    /// matched to requests in the order the requests were sent, so the
+    /// server's response to this request will still be consumed (and
+    /// discarded) rather than being delivered to a later scout.
     pub fn scout_locations(
         &mut self,
         locations: impl IntoIterator<Item = impl AsLocationId>,
@@ -602,7 +1383,11 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                 self.socket
                     .send(ClientMessage::LocationScouts(LocationScouts {
                         locations,
-                        create_as_hint,
+                        create_as_hint: if self.dry_run {
+                            CreateAsHint::No
+                        } else {
+                            create_as_hint
+                        },
                     }))
             }) {
             Ok(()) => self.location_scout_senders.push_back(sender),
@@ -613,6 +1398,44 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         receiver
     }
 
+    /// Like [scout_locations](Self::scout_locations) with [CreateAsHint::New],
+    /// except that newly-created hints are given `status` rather than the
+    /// server's default status.
+    ///
+    /// For example, passing [HintStatus::NoPriority] lets a client hint items
+    /// the player has seen without marking them as wanted.
+    ///
+    /// If the hints can't be created, the returned receiver resolves to that
+    /// error and the locations aren't scouted.
+    pub fn scout_locations_with_hint_status(
+        &mut self,
+        locations: impl IntoIterator<Item = impl AsLocationId>,
+        status: HintStatus,
+    ) -> oneshot::Receiver<Result<Vec<LocatedItem>, Error>> {
+        let locations = match self.verify_local_locations(locations) {
+            Ok(locations) => locations,
+            Err(err) => {
+                let (sender, receiver) = oneshot::channel();
+                mem::drop(sender.send(Err(err)));
+                return receiver;
+            }
+        };
+
+        // LocationScouts can't set a hint status, so we create the hints
+        // separately and then scout without creating hints. CreateHints ignores
+        // locations that are already hinted, which matches
+        // [CreateAsHint::New].
+        if let Err(err) = self.create_hints_with_options(
+            locations.iter().copied(),
+            CreateHintsOptions::new().status(status),
+        ) {
+            let (sender, receiver) = oneshot::channel();
+            mem::drop(sender.send(Err(err)));
+            return receiver;
+        }
+        self.scout_locations(locations, CreateAsHint::No)
+    }
+
     /// Create hints for the specified locations on the server. Locations that
     /// already have hints will be ignored.
     ///
@@ -640,7 +1463,7 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             locations,
         )
         .and_then(|locations| {
-            self.socket.send(ClientMessage::CreateHints(CreateHints {
+            self.send_mutation(ClientMessage::CreateHints(CreateHints {
                 locations,
                 player: slot,
                 status: options.status,
@@ -669,7 +1492,7 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             .into());
         }
 
-        self.socket.send(ClientMessage::UpdateHint(UpdateHint {
+        self.send_mutation(ClientMessage::UpdateHint(UpdateHint {
             player: player.slot(),
             location,
             status,
@@ -678,18 +1501,41 @@ impl<S: DeserializeOwned + 'static> Client<S> {
 
     /// Notifies the server that the client has the given `status`.
     pub fn set_status(&mut self, status: ClientStatus) -> Result<(), Error> {
-        self.socket
-            .send(ClientMessage::StatusUpdate(StatusUpdate { status }))
+        self.send_mutation(ClientMessage::StatusUpdate(StatusUpdate { status }))
     }
 
     /// Broadcasts `text` to all teammates in the multiworld.
     pub fn say(&mut self, text: String) -> Result<(), Error> {
-        self.socket.send(ClientMessage::Say(Say { text }))
+        self.send_mutation(ClientMessage::Say(Say { text }))
+    }
+
+    /// Broadcasts `text` to all teammates in the multiworld, addressed to the
+    /// player whose slot name is `player_name`.
+    ///
+    /// This prefixes the message with that player's current
+    /// [alias](Player::alias) so that it matches the name other clients
+    /// display for them. If no player has that slot name, `player_name` is
+    /// used as-is. The server displays chat messages verbatim, so the name
+    /// isn't quoted even if it contains spaces.
+    ///
+    /// Archipelago doesn't support private messages, so everyone in the
+    /// multiworld will still see the text.
+    pub fn say_to(&mut self, player_name: impl AsRef<str>, text: String) -> Result<(), Error> {
+        let player_name = player_name.as_ref();
+        let name = self
+            .player_by_name(player_name)
+            .map_or(player_name, |player| player.alias())
+            .to_string();
+        self.say(format!("{name}: {text}"))
     }
 
     /// Broadcasts `data` to other clients in the multiworld.
+    ///
+    /// If `options` targets games or slots that don't exist in this
+    /// multiworld, this logs a warning but still sends the bounce.
     pub fn bounce(&mut self, data: serde_json::Value, options: BounceOptions) -> Result<(), Error> {
-        self.socket.send(ClientMessage::Bounce(Bounce {
+        self.warn_unknown_bounce_targets(options.games.as_ref(), options.slots.as_ref());
+        self.send_mutation(ClientMessage::Bounce(Bounce {
             games: options.games,
             slots: options.slots,
             tags: options.tags,
@@ -699,10 +1545,19 @@ impl<S: DeserializeOwned + 'static> Client<S> {
 
     /// Notifies the player's teammates with death link enabled that the player
     /// has died.
+    ///
+    /// If `options` targets games or slots that don't exist in this
+    /// multiworld, this logs a warning but still sends the death link.
     pub fn death_link(&mut self, options: DeathLinkOptions) -> Result<(), Error> {
+        self.warn_unknown_bounce_targets(options.games.as_ref(), options.slots.as_ref());
+        let source = options
+            .source
+            .clone()
+            .unwrap_or_else(|| self.this_player().alias().to_string());
+        let cause = options.render_cause(&source);
         let mut tags = options.tags.unwrap_or_default();
         tags.insert(*DEATH_LINK_TAG);
-        self.socket.send(ClientMessage::Bounce(Bounce {
+        self.send_mutation(ClientMessage::Bounce(Bounce {
             games: options.games,
             slots: options.slots,
             tags: Some(tags),
@@ -710,10 +1565,8 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                 // Subtract the server delay so that we're sending our best
                 // guess of the time on the server when the death occurred.
                 time: options.time.unwrap_or(SystemTime::now()) - self.server_skew,
-                cause: options.cause,
-                source: options
-                    .source
-                    .unwrap_or_else(|| self.this_player().alias().to_string()),
+                cause,
+                source,
             }),
         }))
     }
@@ -725,6 +1578,10 @@ impl<S: DeserializeOwned + 'static> Client<S> {
     /// server. See [the protocol documentation] for details.
     ///
     /// [the protocol documentation]: https://github.com/ArchipelagoMW/Archipelago/blob/main/docs/network%20protocol.md#get
+    ///
+    /// It's safe to drop the returned receiver to ignore the result. The
+    /// server's response to this request will still be consumed (and
+    /// discarded) rather than being delivered to a later `get`.
     pub fn get(
         &mut self,
         keys: impl IntoIterator<Item = impl Into<String>>,
@@ -733,12 +1590,66 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         match self.socket.send(ClientMessage::Get(Get {
             keys: keys.into_iter().map(|k| k.into()).collect(),
         })) {
-            Ok(()) => self.get_senders.push_back(sender),
+            Ok(()) => self.get_senders.push_back(GetSender::Raw(sender)),
+            Err(err) => mem::drop(sender.send(Err(err))),
+        }
+        receiver
+    }
+
+    /// Retrieves all the hints involving `slot` on the local player's team,
+    /// either because the hinted item is in `slot`'s world or because `slot`
+    /// will receive it.
+    ///
+    /// This is the way to load hints that were created before this client
+    /// connected, since [Print::Hint] is only emitted for new hints.
+    pub fn get_hints_for_slot(&mut self, slot: u32) -> oneshot::Receiver<Result<Vec<Hint>, Error>> {
+        self.request_hints(slot, GetSender::Hints)
+    }
+
+    /// Retrieves the hints for progression items that the local player will
+    /// receive and that haven't been found yet.
+    ///
+    /// These are the locations, according to hints, that are known to hold
+    /// this player's progression. Hints for locations in this player's own
+    /// world are only included if the location is still unchecked. For other
+    /// worlds, this relies on the server's record of whether the item has been
+    /// found.
+    pub fn my_hinted_unchecked_receipts(&mut self) -> oneshot::Receiver<Result<Vec<Hint>, Error>> {
+        self.request_hints(self.slot(), GetSender::HintedReceipts)
+    }
+
+    /// Sends a `Get` request for `slot`'s hints and registers the [GetSender]
+    /// that `make_sender` creates to handle the response.
+    fn request_hints(
+        &mut self,
+        slot: u32,
+        make_sender: impl FnOnce(String, oneshot::Sender<Result<Vec<Hint>, Error>>) -> GetSender,
+    ) -> oneshot::Receiver<Result<Vec<Hint>, Error>> {
+        let (sender, receiver) = oneshot::channel();
+        let key = format!("_read_hints_{}_{}", self.team(), slot);
+        match self.socket.send(ClientMessage::Get(Get {
+            keys: vec![key.clone()],
+        })) {
+            Ok(()) => self.get_senders.push_back(make_sender(key, sender)),
             Err(err) => mem::drop(sender.send(Err(err))),
         }
         receiver
     }
 
+    /// Returns whether `hint` is for an unfound progression item that the
+    /// local player will receive from a location that isn't known to be
+    /// checked.
+    fn is_hinted_unchecked_receipt(&self, hint: &Hint) -> bool {
+        let item = hint.item();
+        !hint.is_found()
+            && item.is_progression()
+            && item.receiver().slot() == self.slot()
+            && (item.sender().slot() != self.slot()
+                || self
+                    .local_unchecked_locations
+                    .contains(&item.location().id()))
+    }
+
     /// Sets custom data in the server's data store. The specific structure of
     /// the data is up to the clients that set it.
     ///
@@ -751,7 +1662,7 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         value: serde_json::Value,
         emit_event: bool,
     ) -> Result<(), Error> {
-        self.socket.send(ClientMessage::Set(Set {
+        self.send_mutation(ClientMessage::Set(Set {
             key: key.into(),
             default: serde_json::Value::Null,
             operations: vec![DataStorageOperation::Replace(value)],
@@ -759,6 +1670,56 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         }))
     }
 
+    /// Sets multiple keys in the server's data store at once. This is
+    /// equivalent to calling [set](Self::set) for each entry.
+    ///
+    /// The Archipelago protocol only allows one key per `Set` command, but
+    /// this sends all the commands to the server in a single WebSocket frame,
+    /// which is more efficient than sending them separately.
+    pub fn set_many(
+        &mut self,
+        entries: impl IntoIterator<Item = (impl Into<String>, serde_json::Value)>,
+        emit_event: bool,
+    ) -> Result<(), Error> {
+        let messages = entries
+            .into_iter()
+            .map(|(key, value)| {
+                ClientMessage::Set(Set {
+                    key: key.into(),
+                    default: serde_json::Value::Null,
+                    operations: vec![DataStorageOperation::Replace(value)],
+                    want_reply: emit_event,
+                })
+            })
+            .collect::<Vec<_>>();
+        if messages.is_empty() {
+            return Ok(());
+        }
+        self.send_mutations(&messages)
+    }
+
+    /// Sets the given `entries` in a map stored in the server's data store,
+    /// leaving other entries in the map as-is. If the key doesn't already have
+    /// a value, it's treated as an empty map.
+    ///
+    /// This is a shorthand for [change](Self::change) with
+    /// [DataStorageOperation::Update]. If `emit_event` is `true`,
+    /// [update](Self::update) will eventually emit [Event::KeyChanged] for this
+    /// key, even if it's not otherwise being watched.
+    pub fn update_map(
+        &mut self,
+        key: impl Into<String>,
+        entries: HashMap<String, serde_json::Value>,
+        emit_event: bool,
+    ) -> Result<(), Error> {
+        self.change(
+            key,
+            serde_json::Value::Object(Default::default()),
+            [DataStorageOperation::Update(entries)],
+            emit_event,
+        )
+    }
+
     /// Changes custom data in the server's data store. The specific structure
     /// of the data is up to the clients that set it.
     ///
@@ -775,7 +1736,7 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         operations: impl IntoIterator<Item = DataStorageOperation>,
         emit_event: bool,
     ) -> Result<(), Error> {
-        self.socket.send(ClientMessage::Set(Set {
+        self.send_mutation(ClientMessage::Set(Set {
             key: key.into(),
             default,
             operations: operations.into_iter().collect(),
@@ -794,6 +1755,24 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         }))
     }
 
+    /// Sends [message], which modifies the server's state, unless this client
+    /// is in dry-run mode. In dry-run mode, the message is logged instead.
+    fn send_mutation(&mut self, message: ClientMessage) -> Result<(), Error> {
+        self.send_mutations(&[message])
+    }
+
+    /// Like [send_mutation], but sends all of [messages] in a single frame.
+    fn send_mutations(&mut self, messages: &[ClientMessage]) -> Result<(), Error> {
+        if self.dry_run {
+            for message in messages {
+                log::info!("[dry run] Not sending {message:?}");
+            }
+            Ok(())
+        } else {
+            self.socket.send_all(messages)
+        }
+    }
+
     /// Converts [locations] to a vector and verifies that they're valid for the
     /// current game.
     fn verify_local_locations(
@@ -827,6 +1806,31 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             .collect()
     }
 
+    /// Logs a warning for any of `games` or `slots` that don't exist in this
+    /// multiworld, since a bounce targeting them won't reach anyone.
+    fn warn_unknown_bounce_targets(&self, games: Option<&UstrSet>, slots: Option<&HashSet<u32>>) {
+        for game in games.into_iter().flatten() {
+            if !self.games.contains_key(game) {
+                log::warn!("Bounce targets game \"{game}\", which isn't in this multiworld");
+            }
+        }
+
+        for slot in slots.into_iter().flatten() {
+            if self.teammate(*slot).is_none() {
+                log::warn!("Bounce targets slot {slot}, which isn't in this multiworld");
+            }
+        }
+    }
+
+    /// Returns whether an incoming death link should be suppressed because it
+    /// arrived within [death_link_dedup] of the last one.
+    fn is_duplicate_death_link(&self) -> bool {
+        match (self.death_link_dedup, self.last_death_link) {
+            (Some(window), Some(last)) => last.elapsed() < window,
+            _ => false,
+        }
+    }
+
     /// Returns the [Player] for [slot] on the current team and verifies that
     /// it's a valid slot number.
     fn verify_teammate(&self, slot: u32) -> Result<&Player, Error> {
@@ -844,6 +1848,10 @@ impl<S: DeserializeOwned + 'static> Client<S> {
     /// Typically a caller that's integrated Archipelago into a game loop will
     /// call this once each frame.
     ///
+    /// If [ConnectionOptions::max_messages_per_update] was set, this processes
+    /// at most that many messages from the server and leaves the rest for
+    /// subsequent calls.
+    ///
     /// Most errors are fatal, and if emitted mean that the client will not emit
     /// any more events and should be discarded and reconnected. Some
     /// (specifically [Error::ProtocolError]s) are recoverable, though, and the
@@ -851,14 +1859,19 @@ impl<S: DeserializeOwned + 'static> Client<S> {
     /// it's not dropped. You can detect which errors are fatal using
     /// [Error.is_fatal].
     pub fn update(&mut self) -> Vec<Event> {
-        self.socket
-            .recv_all()
-            .into_iter()
-            .filter_map(|message| match message {
-                Ok(message) => self.handle_message(message),
-                Err(err) => Some(Event::Error(err)),
-            })
-            .collect()
+        self.poll_cache_writes();
+        let mut events = Vec::from(mem::take(&mut self.pending_events));
+        for message in self.socket.recv_all(self.max_messages_per_update) {
+            match message {
+                Ok(message) => events.extend(self.handle_message(message)),
+                Err(err) => events.push(Event::Error(err)),
+            }
+            events.extend(self.pending_events.drain(..));
+        }
+        for event in &events {
+            self.run_event_callbacks(event);
+        }
+        events
     }
 
     /// Returns a single pending [Event] from the Archipelago server and updates
@@ -876,18 +1889,85 @@ impl<S: DeserializeOwned + 'static> Client<S> {
     /// it's not dropped. You can detect which errors are fatal using
     /// [Error.is_fatal].
     pub fn try_next_event(&mut self) -> Option<Event> {
-        self.socket.try_recv().and_then(|message| match message {
-            Ok(message) => self.handle_message(message),
-            Err(err) => Some(Event::Error(err)),
-        })
+        self.poll_cache_writes();
+        let event = self.pending_events.pop_front().or_else(|| {
+            self.socket.try_recv().and_then(|message| match message {
+                Ok(message) => self.handle_message(message),
+                Err(err) => Some(Event::Error(err)),
+            })
+        })?;
+        self.run_event_callbacks(&event);
+        Some(event)
+    }
+
+    /// Registers `callback` to be called with each [Event] this client emits.
+    ///
+    /// Callbacks are invoked in the order they were registered by
+    /// [update](Self::update), [try_next_event](Self::try_next_event), and
+    /// [wait_for](Self::wait_for), in addition to those methods returning the
+    /// events as usual. This is useful for integrating with engines that use
+    /// callback-based event systems rather than polling.
+    pub fn on_event(&mut self, callback: impl FnMut(&Event) + Send + 'static) {
+        self.event_callbacks.push(Box::new(callback));
+    }
+
+    /// Passes [event] to all callbacks registered with [on_event].
+    fn run_event_callbacks(&mut self, event: &Event) {
+        for callback in &mut self.event_callbacks {
+            callback(event);
+        }
+    }
+
+    /// Returns a Future that processes events from the server until one matches
+    /// `predicate`, then resolves to that event.
+    ///
+    /// This is an alternative to polling [update](Self::update) for callers
+    /// such as bots and scripts that are happy to wait for a particular event.
+    /// Events that don't match `predicate` still update the client's state, but
+    /// are otherwise discarded. The Future waits for the socket to become
+    /// readable between messages rather than busy-looping.
+    ///
+    /// If the client encounters a fatal error, the Future resolves to that
+    /// [Event::Error] even if it doesn't match `predicate`, since no more
+    /// events will arrive afterwards.
+    pub async fn wait_for(&mut self, predicate: impl Fn(&Event) -> bool) -> Event {
+        loop {
+            let event = if let Some(event) = self.pending_events.pop_front() {
+                event
+            } else {
+                match self.socket.try_recv() {
+                    Some(Ok(message)) => match self.handle_message(message) {
+                        Some(event) => event,
+                        None => continue,
+                    },
+                    Some(Err(err)) => Event::Error(err),
+                    None => {
+                        if let Err(err) = self.socket.readable().await {
+                            return Event::Error(err);
+                        }
+                        continue;
+                    }
+                }
+            };
+
+            self.run_event_callbacks(&event);
+            if predicate(&event) || matches!(&event, Event::Error(err) if err.is_fatal()) {
+                return event;
+            }
+        }
     }
 
     /// Handles a single message, converting it into an event for the user if
     /// necessary.
-    fn handle_message(&mut self, message: ServerMessage<S>) -> Option<Event> {
+    fn handle_message(&mut self, message: ServerMessage) -> Option<Event> {
         match message {
             ServerMessage::RawPrint(print) => Some(match Print::hydrate(print, self) {
-                Ok(print) => Event::Print(print),
+                Ok(print) => {
+                    if let Print::Join { tags, .. } | Print::TagsChanged { tags, .. } = &print {
+                        self.player_tags.extend(tags.iter().map(|t| Ustr::from(t)));
+                    }
+                    Event::Print(print)
+                }
                 Err(err) => Event::Error(err),
             }),
 
@@ -900,7 +1980,14 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             }
 
             ServerMessage::ReceivedItems(ReceivedItems { index, items }) => {
+                // A full list of items that we didn't ask for (and that isn't
+                // the initial list sent after connecting) means the server has
+                // reset our items out from under us.
+                let resynced =
+                    index == 0 && self.sync_requests == 0 && !self.received_items.is_empty();
+                let skip = self.skip_items_before.take().filter(|_| index == 0);
                 if index == 0 {
+                    self.sync_requests = self.sync_requests.saturating_sub(1);
                     self.received_items.clear();
                 } else if index > self.received_items.len() {
                     // If the index of the item we just received doesn't
@@ -910,12 +1997,21 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                     return self.sync().err().map(Event::Error);
                 }
 
+                // ReceivedItems only ever contains items for the connected
+                // slot. When an item is sent to an item link group, the server
+                // sends a copy to each member slot individually rather than
+                // reporting the group as the receiver, so the local player is
+                // always the correct receiver here.
                 let receiver = &self.players[&self.player_key];
                 let receiver_game = self.this_game();
                 let items_or_err = items
                     .into_iter()
                     .enumerate()
                     .map(|(i, network)| {
+                        // NetworkItem.player is a bare slot number. Each team
+                        // plays its own independent copy of the multiworld, so
+                        // items are never sent across teams and the sender is
+                        // always on the local player's team.
                         let sender = self.teammate_arc(network.player)?;
                         let sender_game = self.game_or_err(sender.game())?;
                         let item = LocatedItem::hydrate_with_games(
@@ -929,13 +2025,30 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                     })
                     .collect::<Result<Vec<ReceivedItem>, Error>>();
 
-                Some(match items_or_err {
+                match items_or_err {
                     Ok(items) => {
+                        let end = index + items.len();
+                        self.last_received_index = Some(end);
                         self.received_items.extend(items);
-                        Event::ReceivedItems(index)
+                        match skip {
+                            Some(skip) if skip == end => None,
+                            Some(skip) if skip < end => Some(Event::ReceivedItems(skip)),
+                            Some(skip) => {
+                                log::warn!(
+                                    "Expected at least {skip} received items but the server \
+                                     only sent {end}, reporting all of them"
+                                );
+                                Some(Event::ReceivedItems(index))
+                            }
+                            None if resynced => {
+                                self.pending_events.push_back(Event::ReceivedItems(index));
+                                Some(Event::ItemsResynced)
+                            }
+                            None => Some(Event::ReceivedItems(index)),
+                        }
                     }
-                    Err(err) => Event::Error(err),
-                })
+                    Err(err) => Some(Event::Error(err)),
+                }
             }
 
             ServerMessage::LocationInfo(LocationInfo { locations }) => {
@@ -956,6 +2069,16 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                         )
                     })
                     .collect::<Result<Vec<LocatedItem>, Error>>();
+                if let Ok(items) = &locations_or_err {
+                    for item in items {
+                        self.scouted_items
+                            .insert((item.sender().slot(), item.location().id()), item.clone());
+                    }
+                }
+                // The server answers scouts in order, so this response belongs
+                // to the oldest pending scout even if its receiver has since
+                // been dropped. In that case, `send()` fails and the response
+                // is discarded, which keeps later scouts correctly aligned.
                 if let Some(sender) = self.location_scout_senders.pop_front() {
                     mem::drop(sender.send(locations_or_err));
                     None
@@ -966,6 +2089,17 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                 }
             }
 
+            ServerMessage::Bounced(Bounced {
+                tags,
+                data: BounceData::Generic(_),
+                ..
+            }) if self.bounce_filter.as_ref().is_some_and(|filter| {
+                tags.as_ref().is_none_or(|tags| tags.is_disjoint(filter))
+            }) =>
+            {
+                None
+            }
+
             ServerMessage::Bounced(Bounced {
                 games,
                 slots,
@@ -978,37 +2112,86 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                 data,
             }),
 
+            ServerMessage::Bounced(Bounced {
+                data: BounceData::DeathLink(_),
+                ..
+            }) if self.is_duplicate_death_link() => None,
+
             ServerMessage::Bounced(Bounced {
                 games,
                 slots,
                 tags,
                 data: BounceData::DeathLink(data),
-            }) => Some(Event::DeathLink {
-                games,
-                slots,
-                tags: tags.unwrap(),
-                // We assume other clients try to normalize the time to
-                // be the time on the server when the death occurred, so
-                // add the server delay to translate that back into the
-                // time on the client.
-                time: data.time + self.server_skew,
-                server_time: data.time,
-                cause: data.cause,
-                source: data.source,
-            }),
-
-            ServerMessage::InvalidPacket(InvalidPacket { text }) => {
-                Some(Event::Error(Error::InvalidPacket(text)))
+            }) => {
+                self.last_death_link = Some(Instant::now());
+                // We assume other clients try to normalize the time to be the
+                // time on the server when the death occurred, so add the
+                // server delay to translate that back into the time on the
+                // client.
+                let time = data.time + self.server_skew;
+                Some(Event::DeathLink {
+                    games,
+                    slots,
+                    tags: tags.unwrap(),
+                    time,
+                    time_ago: SystemTime::now().duration_since(time).ok(),
+                    server_time: data.time,
+                    cause: data.cause,
+                    source: data.source,
+                })
             }
 
-            ServerMessage::Retrieved(Retrieved { keys }) => {
-                if let Some(sender) = self.get_senders.pop_front() {
-                    mem::drop(sender.send(Ok(keys)));
+            ServerMessage::InvalidPacket(InvalidPacket { text, original_cmd }) => {
+                // If the server rejected a scout, it won't send a LocationInfo
+                // for it, so we resolve the pending request with the error
+                // instead to keep later responses lined up with their requests.
+                if original_cmd.as_deref() == Some("LocationScouts")
+                    && let Some(sender) = self.location_scout_senders.pop_front()
+                {
+                    mem::drop(sender.send(Err(Error::InvalidPacket(text))));
                     None
                 } else {
-                    Some(Event::Error(
+                    Some(Event::Error(Error::InvalidPacket(text)))
+                }
+            }
+
+            ServerMessage::Retrieved(Retrieved { keys }) => {
+                match self.get_senders.pop_front() {
+                    Some(GetSender::RaceMode) => {
+                        self.race_mode = keys
+                            .get(RACE_MODE_KEY)
+                            .map(|value| value.as_i64().is_some_and(|v| v != 0));
+                        // If the server didn't send any items on connect,
+                        // there's no initial replay to skip, so don't skip part
+                        // of a later resync instead.
+                        self.skip_items_before = None;
+                        (!mem::replace(&mut self.caught_up, true)).then_some(Event::CatchUpComplete)
+                    }
+                    Some(GetSender::Raw(sender)) => {
+                        mem::drop(sender.send(Ok(keys)));
+                        None
+                    }
+                    Some(GetSender::Hints(key, sender)) => {
+                        let mut keys = keys;
+                        let value = keys.remove(&key).unwrap_or_default();
+                        mem::drop(sender.send(Hint::from_storage(value, self)));
+                        None
+                    }
+                    Some(GetSender::HintedReceipts(key, sender)) => {
+                        let mut keys = keys;
+                        let value = keys.remove(&key).unwrap_or_default();
+                        let hints = Hint::from_storage(value, self).map(|hints| {
+                            hints
+                                .into_iter()
+                                .filter(|hint| self.is_hinted_unchecked_receipt(hint))
+                                .collect()
+                        });
+                        mem::drop(sender.send(hints));
+                        None
+                    }
+                    None => Some(Event::Error(
                         ProtocolError::ResponseWithoutRequest("Retrieved").into(),
-                    ))
+                    )),
                 }
             }
 
@@ -1051,12 +2234,69 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                 ProtocolError::ResponseWithoutRequest("Connected").into(),
             )),
 
+            ServerMessage::DataPackage(DataPackage { data }) if self.data_package_requests > 0 => {
+                self.data_package_requests -= 1;
+                Some(match self.adopt_data_package(data) {
+                    Ok(field) => Event::Updated(vec![field]),
+                    Err(err) => Event::Error(err),
+                })
+            }
+
             ServerMessage::DataPackage(_) => Some(Event::Error(
                 ProtocolError::ResponseWithoutRequest("DataPackage").into(),
             )),
         }
     }
 
+    /// Verifies [data_package] against the server's advertised checksums,
+    /// stores it in the cache, and then replaces this client's games with it.
+    ///
+    /// If any game's checksum doesn't match, this returns an error without
+    /// changing anything.
+    fn adopt_data_package(
+        &mut self,
+        data_package: DataPackageObject,
+    ) -> Result<UpdatedField, Error> {
+        for (game, data) in &data_package.games {
+            verify_checksum(*game, data, &self.datapackage_checksums)?;
+        }
+
+        if let Some(cache) = &self.cache {
+            let cache = cache.clone();
+            let games = data_package.games.clone();
+            self.cache_writes.push(Box::pin(
+                async move { cache.store_data_packages(&games).await },
+            ));
+            self.poll_cache_writes();
+        }
+
+        Ok(self.replace_games(data_package))
+    }
+
+    /// Makes as much progress as possible on [cache_writes] without blocking,
+    /// and drops any that have finished.
+    fn poll_cache_writes(&mut self) {
+        self.cache_writes
+            .retain_mut(|write| try_future(write).is_none());
+    }
+
+    /// Replaces this client's games with those in [data_package] and returns
+    /// the [UpdatedField] describing the change.
+    fn replace_games(&mut self, data_package: DataPackageObject) -> UpdatedField {
+        // Inserting into [games] may move its values, so we need to re-derive
+        // [game] afterwards.
+        let this_game = self.this_game().name();
+        let old_games = data_package
+            .games
+            .into_iter()
+            .filter_map(|(name, data)| self.games.insert(name, Game::hydrate(name, data)))
+            .collect();
+        self.game = ptr::from_ref(self.assert_game(this_game));
+        // Scouted items refer to the old games' item and location names.
+        self.clear_scout_cache();
+        UpdatedField::GameData(old_games)
+    }
+
     /// Updates the room with the information in [update].
     fn update_room(&mut self, update: RoomUpdate) -> Result<Event, Error> {
         // Check for errors before making any changes so we don't end up in an
@@ -1066,27 +2306,38 @@ impl<S: DeserializeOwned + 'static> Client<S> {
             .map(|ids| {
                 let game = self.this_game();
                 ids.into_iter()
+                    // The server echoes back checks sent through
+                    // [mark_checked_unchecked], which we can't resolve to
+                    // locations.
+                    .filter(|id| !self.unverified_checked_locations.contains(id))
                     .map(|id| game.location_or_err(id))
                     .collect::<Result<Vec<_>, Error>>()
             })
             .transpose()?;
 
+        let mut new_players = Vec::new();
         let updated_players = update
             .players
             .map(|players| {
-                players
-                    .into_iter()
-                    .filter_map(|new| {
-                        self.players
-                            .get(&(new.team, new.slot))
+                let mut updated = Vec::new();
+                for network in players {
+                    if let Some(old) = self.players.get(&(network.team, network.slot)) {
+                        updated.extend(old.with_alias(network.alias));
+                        continue;
+                    }
+
+                    // A player we haven't seen before, such as a slot that
+                    // wasn't in the initial `Connected` player list.
+                    let slot_info =
+                        self.slot_info
+                            .get(&network.slot)
                             .ok_or(ProtocolError::MissingPlayer {
-                                team: new.team,
-                                slot: new.slot,
-                            })
-                            .map(|old| old.with_alias(new.alias))
-                            .transpose()
-                    })
-                    .collect::<Result<Vec<_>, ProtocolError>>()
+                                team: network.team,
+                                slot: network.slot,
+                            })?;
+                    new_players.push(Player::hydrate(network, slot_info, &self.players)?);
+                }
+                Ok::<_, Error>(updated)
             })
             .transpose()?;
 
@@ -1121,19 +2372,43 @@ impl<S: DeserializeOwned + 'static> Client<S> {
         }
 
         if let Some(hint_points) = update.hint_points {
-            updated.push(UpdatedField::HintPoints(mem::replace(
-                &mut self.hint_points,
-                hint_points,
-            )))
+            let old = mem::replace(&mut self.hint_points, hint_points);
+            if old != hint_points {
+                self.pending_events.push_back(Event::HintPointsChanged {
+                    old,
+                    new: hint_points,
+                });
+            }
+            updated.push(UpdatedField::HintPoints(old))
+        }
+
+        if !new_players.is_empty() {
+            let mut added = Vec::with_capacity(new_players.len());
+            for player in new_players {
+                let player = Arc::new(player);
+                self.players
+                    .insert((player.team(), player.slot()), player.clone());
+                added.push(player);
+            }
+            updated.push(UpdatedField::PlayersAdded(added));
         }
 
         if let Some(players) = updated_players {
-            updated.push(UpdatedField::Players(
-                players
-                    .into_iter()
-                    .filter_map(|p| self.players.insert((p.team(), p.slot()), p.into()))
-                    .collect(),
-            ))
+            let mut old_players = Vec::with_capacity(players.len());
+            for player in players {
+                let player = Arc::new(player);
+                if let Some(old) = self
+                    .players
+                    .insert((player.team(), player.slot()), player.clone())
+                {
+                    self.pending_events.push_back(Event::PlayerRenamed {
+                        player,
+                        old_alias: old.alias().to_string(),
+                    });
+                    old_players.push(old);
+                }
+            }
+            updated.push(UpdatedField::Players(old_players))
         }
 
         if let Some(locations) = checked_locations {
@@ -1142,7 +2417,10 @@ impl<S: DeserializeOwned + 'static> Client<S> {
                     .into_iter()
                     // Omit locations that we already know are checked from
                     // local information.
-                    .filter(|loc| !self.local_locations_checked.insert(loc.id(), true).unwrap())
+                    .filter(|loc| {
+                        self.local_checked_locations.insert(loc.id());
+                        self.local_unchecked_locations.remove(&loc.id())
+                    })
                     .collect(),
             ))
         }
@@ -1151,6 +2429,20 @@ impl<S: DeserializeOwned + 'static> Client<S> {
     }
 }
 
+/// Returns an error if `data`'s checksum doesn't match the one the server
+/// advertised for `game` in `checksums`.
+fn verify_checksum(game: Ustr, data: &GameData, checksums: &UstrMap<String>) -> Result<(), Error> {
+    match checksums.get(&game) {
+        Some(expected) if *expected != data.checksum => Err(ProtocolError::ChecksumMismatch {
+            game,
+            expected: expected.clone(),
+            actual: data.checksum.clone(),
+        }
+        .into()),
+        _ => Ok(()),
+    }
+}
+
 // The only reason Client doesn't automatically implement [Unpin] is that S
 // might not implement it (although being decoded from JSON it probably does).
 // Since we treat slot data as immutable anyway, we can guarantee that nothing
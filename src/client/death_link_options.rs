@@ -14,6 +14,7 @@ pub struct DeathLinkOptions {
     pub(crate) time: Option<SystemTime>,
     pub(crate) source: Option<String>,
     pub(crate) cause: Option<String>,
+    pub(crate) cause_template: Option<(String, String)>,
 }
 
 impl DeathLinkOptions {
@@ -61,7 +62,7 @@ impl DeathLinkOptions {
     ///
     /// By default, no cause is provided.
     pub fn source(mut self, source: String) -> Self {
-        self.cause = Some(source);
+        self.source = Some(source);
         self
     }
 
@@ -71,6 +72,85 @@ impl DeathLinkOptions {
     /// By default, no cause is provided.
     pub fn cause(mut self, cause: String) -> Self {
         self.cause = Some(cause);
+        self.cause_template = None;
         self
     }
+
+    /// Sets the cause of death by filling in `template`. Any instances of
+    /// `{source}` in `template` are replaced with the name of the player who
+    /// died (see [source](Self::source)), and any instances of `{killer}` are
+    /// replaced with `killer`.
+    ///
+    /// For example, `cause_template("{source} was slain by {killer}.", "the
+    /// Abyss Watchers")` might produce "Natalie was slain by the Abyss
+    /// Watchers."
+    ///
+    /// This overrides [cause](Self::cause), and vice versa.
+    pub fn cause_template(
+        mut self,
+        template: impl Into<String>,
+        killer: impl Into<String>,
+    ) -> Self {
+        self.cause_template = Some((template.into(), killer.into()));
+        self.cause = None;
+        self
+    }
+
+    /// Returns the cause of death for a death link whose source is `source`.
+    pub(crate) fn render_cause(&self, source: &str) -> Option<String> {
+        match &self.cause_template {
+            Some((template, killer)) => Some(
+                template
+                    .replace("{source}", source)
+                    .replace("{killer}", killer),
+            ),
+            None => self.cause.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_cause_default() {
+        assert_eq!(DeathLinkOptions::new().render_cause("Natalie"), None);
+    }
+
+    #[test]
+    fn render_cause_plain() {
+        let options = DeathLinkOptions::new().cause("Natalie fell.".into());
+        assert_eq!(
+            options.render_cause("Someone else").as_deref(),
+            Some("Natalie fell.")
+        );
+    }
+
+    #[test]
+    fn render_cause_template() {
+        let options = DeathLinkOptions::new()
+            .cause_template("{source} was slain by {killer}. RIP {source}.", "a dragon");
+        assert_eq!(
+            options.render_cause("Natalie").as_deref(),
+            Some("Natalie was slain by a dragon. RIP Natalie.")
+        );
+    }
+
+    #[test]
+    fn render_cause_overrides() {
+        let options = DeathLinkOptions::new()
+            .cause("Natalie fell.".into())
+            .cause_template("{source} was slain by {killer}.", "a dragon");
+        assert_eq!(
+            options.render_cause("Natalie").as_deref(),
+            Some("Natalie was slain by a dragon.")
+        );
+
+        let options = options.cause("Natalie fell.".into());
+        assert_eq!(
+            options.render_cause("Natalie").as_deref(),
+            Some("Natalie fell.")
+        );
+    }
 }
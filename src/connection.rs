@@ -1,11 +1,12 @@
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll, Waker};
-use std::{fmt, pin::Pin};
+use std::{fmt, mem, pin::Pin};
 
 use serde::de::DeserializeOwned;
 use smol::future::FutureExt;
 use ustr::Ustr;
 
-use crate::{Client, ConnectionOptions, Event, error::*};
+use crate::{Client, ConnectionOptions, Event, deserialize_slot_data, error::*};
 
 mod socket;
 
@@ -22,12 +23,44 @@ pub(crate) use socket::*;
 /// `Connected` message. By default, it will decode the slot data as a
 /// dynamically-typed JSON blob. If `S = ()`, this will not request slot data
 /// from the server at all.
+///
+/// A connection can't be cloned, since it owns the underlying socket. It's
+/// [Send] but not [Sync], so to share it with other threads (for example, to
+/// let a UI thread read from the [Client] while the game thread updates it),
+/// wrap it in an `Arc<Mutex<Connection>>` and use [client](Self::client) to
+/// access the client's read-only accessors while the lock is held.
 #[derive(Default)]
 pub struct Connection<S: DeserializeOwned + Send + 'static = serde_json::Value> {
     /// The current state of the connection.
     state: ConnectionState<S>,
+
+    /// The URL this connection was created with, or the URL it connected to
+    /// once it's connected.
+    url: String,
+
+    /// All the URLs this connection was created with, in the order they should
+    /// be tried. This is used by [Connection.retry_with_password].
+    urls: Vec<String>,
+
+    /// The player name this connection was created with.
+    name: Ustr,
+
+    /// The game this connection was created with.
+    game: Option<Ustr>,
+
+    /// The options this connection was created with, used by
+    /// [Connection.retry_with_password].
+    options: ConnectionOptions,
+
+    /// The slot data decoder this connection was created with, used by
+    /// [Connection.retry_with_password]. If this is `None`, slot data is
+    /// deserialized directly.
+    decode_slot_data: Option<SlotDataDecoder<S>>,
 }
 
+/// A callback that converts raw slot data into `S`.
+type SlotDataDecoder<S> = Arc<dyn Fn(&serde_json::Value) -> Result<S, Error> + Send + Sync>;
+
 impl<S: DeserializeOwned + Send + 'static> Connection<S> {
     /// Begins a connection to the Archipelago server at `url`, with the given
     /// `game` (which must match the apworld's name) and player `name` (which
@@ -37,9 +70,10 @@ impl<S: DeserializeOwned + Send + 'static> Connection<S> {
     /// followed by `ws://`. If it doesn't have a port, it defaults to the
     /// Archipelago default port 38281.
     ///
-    /// If `game` is `None`, [ConnectionOptions::tags] must contain at least one
-    /// of `HintGame`, `Tracker` or `TextOnly`. In this case, the server won't
-    /// validate that the slot you connect to is playing a particular game.
+    /// If `game` is `None` or empty, [ConnectionOptions::tags] must contain at
+    /// least one of `HintGame`, `Tracker` or `TextOnly`. In this case, the
+    /// server won't validate that the slot you connect to is playing a
+    /// particular game.
     ///
     /// See [ConnectionOptions] for details about optional arguments and their
     /// defaults.
@@ -49,16 +83,157 @@ impl<S: DeserializeOwned + Send + 'static> Connection<S> {
         game: Option<impl Into<Ustr>>,
         options: ConnectionOptions,
     ) -> Self {
-        Connection {
-            state: ConnectionState::Connecting(Connecting(Box::pin(Client::connect(
-                url.into(),
-                name.into(),
-                game.map(|g| g.into()),
+        Self::start(
+            vec![url.into()],
+            name.into(),
+            game.map(|g| g.into()),
+            options,
+            None,
+        )
+    }
+
+    /// Like [new](Self::new), but uses `decode_slot_data` to convert the raw
+    /// slot data into `S` rather than deserializing it directly.
+    ///
+    /// This is useful for apworlds that version their slot data, since it
+    /// allows the caller to try multiple schemas in whatever order they choose.
+    /// If `decode_slot_data` returns an error, the connection fails with that
+    /// error.
+    pub fn new_with_slot_data_decoder(
+        url: impl Into<String>,
+        name: impl Into<Ustr>,
+        game: Option<impl Into<Ustr>>,
+        options: ConnectionOptions,
+        decode_slot_data: impl Fn(&serde_json::Value) -> Result<S, Error> + Send + Sync + 'static,
+    ) -> Self {
+        Self::start(
+            vec![url.into()],
+            name.into(),
+            game.map(|g| g.into()),
+            options,
+            Some(Arc::new(decode_slot_data)),
+        )
+    }
+
+    /// Begins connecting with the given parameters, trying each of `urls` in
+    /// order as described in [new_with_fallbacks](Self::new_with_fallbacks).
+    ///
+    /// Panics if `urls` is empty.
+    fn start(
+        urls: Vec<String>,
+        name: Ustr,
+        game: Option<Ustr>,
+        options: ConnectionOptions,
+        decode_slot_data: Option<SlotDataDecoder<S>>,
+    ) -> Self {
+        let Some(first_url) = urls.first().cloned() else {
+            return Connection {
+                state: ConnectionState::Disconnected(ArgumentError::NoUrls.into()),
+                url: Default::default(),
+                urls,
+                name,
+                game,
                 options,
-            )))),
+                decode_slot_data,
+            };
+        };
+
+        let progress = ConnectingProgress::default();
+        Connection {
+            state: ConnectionState::Connecting(Connecting {
+                future: Box::pin({
+                    let urls = urls.clone();
+                    let options = options.clone();
+                    let decode_slot_data = decode_slot_data.clone();
+                    let progress = progress.clone();
+                    async move {
+                        let mut urls = urls.into_iter().peekable();
+                        loop {
+                            let url = urls.next().unwrap();
+                            // Each fallback starts the handshake over.
+                            progress.set(ConnectingPhase::OpeningSocket);
+                            let result = Client::connect_with_progress(
+                                url.clone(),
+                                name,
+                                game,
+                                options.clone(),
+                                |value| match &decode_slot_data {
+                                    Some(decode) => decode(value),
+                                    None => deserialize_slot_data(value),
+                                },
+                                &progress,
+                            )
+                            .await;
+                            match result {
+                                Err(err @ (Error::WebSocket(_) | Error::Async(_)))
+                                    if urls.peek().is_some() =>
+                                {
+                                    log::warn!(
+                                        "Failed to connect to {url}, trying next URL: {err}"
+                                    );
+                                }
+                                result => return result,
+                            }
+                        }
+                    }
+                }),
+                progress,
+            }),
+            url: first_url,
+            urls,
+            name,
+            game,
+            options,
+            decode_slot_data,
         }
     }
 
+    /// Like [new](Self::new), but tries each of `urls` in order until one
+    /// successfully connects.
+    ///
+    /// This only moves on to the next URL if the previous one couldn't be
+    /// reached at all. If the server rejects the connection for any other
+    /// reason, such as an invalid slot name, the connection fails immediately.
+    /// If none of the URLs can be reached, this fails with the last URL's
+    /// error. Once connected, [url](Self::url) returns the URL that succeeded.
+    ///
+    /// If `urls` is empty, the connection is immediately disconnected with
+    /// [ArgumentError::NoUrls].
+    pub fn new_with_fallbacks(
+        urls: impl IntoIterator<Item: Into<String>>,
+        name: impl Into<Ustr>,
+        game: Option<impl Into<Ustr>>,
+        options: ConnectionOptions,
+    ) -> Self {
+        let urls = urls.into_iter().map(|u| u.into()).collect::<Vec<String>>();
+        Self::start(urls, name.into(), game.map(|g| g.into()), options, None)
+    }
+
+    /// Starts a new connection with the same parameters as this one, but with
+    /// `password` as the password.
+    ///
+    /// This is useful when the server refuses the connection with
+    /// [ConnectionError::InvalidPassword], so that the player can re-enter the
+    /// password without having to re-enter everything else. This puts the
+    /// connection back into [ConnectionState::Connecting], dropping any
+    /// existing client. For connections created with
+    /// [new_with_fallbacks](Self::new_with_fallbacks), this tries all the URLs
+    /// again in their original order. If there are no URLs to try, such as for
+    /// [Connection::default], the connection is disconnected with
+    /// [ArgumentError::NoUrls].
+    pub fn retry_with_password(&mut self, password: impl Into<String>) {
+        let options = mem::take(&mut self.options).password(password);
+        // If this has no URLs (for example, because it's a
+        // [Connection::default]), this fails with [ArgumentError::NoUrls].
+        *self = Self::start(
+            mem::take(&mut self.urls),
+            self.name,
+            self.game,
+            options,
+            self.decode_slot_data.take(),
+        );
+    }
+
     /// Updates this connection in-place to its next available state.
     ///
     /// This call never blocks, and is expected to be called repeatedly in order
@@ -77,23 +252,26 @@ impl<S: DeserializeOwned + Send + 'static> Connection<S> {
     /// afterwards.
     pub fn update(&mut self) -> Vec<Event> {
         match self.state {
-            ConnectionState::Connecting(Connecting(ref mut future)) => match try_future(future) {
-                Some(Ok(client)) => {
-                    self.state = ConnectionState::Connected(client);
-                    // It's unlikely that any events have come in already, but
-                    // no harm in checking.
-                    let later_events = self.update();
-                    let mut events = Vec::with_capacity(later_events.len() + 1);
-                    events.push(Event::Connected);
-                    events.extend(later_events);
-                    events
-                }
-                Some(Err(err)) => {
-                    self.state = ConnectionState::Disconnected(err);
-                    vec![Event::Error(Error::Elsewhere)]
+            ConnectionState::Connecting(Connecting { ref mut future, .. }) => {
+                match try_future(future) {
+                    Some(Ok(client)) => {
+                        self.url = client.url().to_string();
+                        self.state = ConnectionState::Connected(client);
+                        // It's unlikely that any events have come in already, but
+                        // no harm in checking.
+                        let later_events = self.update();
+                        let mut events = Vec::with_capacity(later_events.len() + 1);
+                        events.push(Event::Connected);
+                        events.extend(later_events);
+                        events
+                    }
+                    Some(Err(err)) => {
+                        self.state = ConnectionState::Disconnected(err);
+                        vec![Event::Error(Error::Elsewhere)]
+                    }
+                    None => vec![],
                 }
-                None => vec![],
-            },
+            }
             ConnectionState::Connected(ref mut client) => {
                 let mut events = client.update();
                 if let Some(Event::Error(error)) =
@@ -126,17 +304,20 @@ impl<S: DeserializeOwned + Send + 'static> Connection<S> {
     /// afterwards.
     pub fn try_next_event(&mut self) -> Option<Event> {
         match self.state {
-            ConnectionState::Connecting(Connecting(ref mut future)) => match try_future(future) {
-                Some(Ok(client)) => {
-                    self.state = ConnectionState::Connected(client);
-                    Some(Event::Connected)
-                }
-                Some(Err(err)) => {
-                    self.state = ConnectionState::Disconnected(err);
-                    Some(Event::Error(Error::Elsewhere))
+            ConnectionState::Connecting(Connecting { ref mut future, .. }) => {
+                match try_future(future) {
+                    Some(Ok(client)) => {
+                        self.url = client.url().to_string();
+                        self.state = ConnectionState::Connected(client);
+                        Some(Event::Connected)
+                    }
+                    Some(Err(err)) => {
+                        self.state = ConnectionState::Disconnected(err);
+                        Some(Event::Error(Error::Elsewhere))
+                    }
+                    None => None,
                 }
-                None => None,
-            },
+            }
             ConnectionState::Connected(ref mut client) => match client.try_next_event() {
                 Some(Event::Error(error)) if error.is_fatal() => {
                     self.state = ConnectionState::Disconnected(error);
@@ -148,6 +329,40 @@ impl<S: DeserializeOwned + Send + 'static> Connection<S> {
         }
     }
 
+    /// The URL that was passed to [new](Self::new).
+    ///
+    /// For connections created with [new_with_fallbacks](Self::new_with_fallbacks),
+    /// this is the first URL until the connection succeeds, and the URL that
+    /// succeeded afterwards.
+    ///
+    /// This is empty for [Connection::default].
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    /// The player name that was passed to [new](Self::new).
+    ///
+    /// This is empty for [Connection::default].
+    pub fn name(&self) -> Ustr {
+        self.name
+    }
+
+    /// The game that was passed to [new](Self::new).
+    ///
+    /// This is `None` for [Connection::default].
+    pub fn game(&self) -> Option<Ustr> {
+        self.game
+    }
+
+    /// The phase of the handshake the connection is in, if it's currently in
+    /// [ConnectionStateType::Connecting].
+    pub fn connecting_phase(&self) -> Option<ConnectingPhase> {
+        match &self.state {
+            ConnectionState::Connecting(connecting) => Some(connecting.phase()),
+            _ => None,
+        }
+    }
+
     /// The current state of the connection.
     pub fn state(&self) -> &ConnectionState<S> {
         &self.state
@@ -179,6 +394,26 @@ impl<S: DeserializeOwned + Send + 'static> Connection<S> {
         }
     }
 
+    /// If this is connected, removes the [Client] and returns it, leaving this
+    /// in [ConnectionState::Disconnected] with [Error::ClientDisconnected].
+    /// Otherwise, returns `None` and leaves this as-is.
+    ///
+    /// The returned client owns the underlying socket, so it remains fully
+    /// usable on its own.
+    pub fn take_client(&mut self) -> Option<Client<S>> {
+        if !self.is_connected() {
+            return None;
+        }
+
+        match mem::replace(
+            &mut self.state,
+            ConnectionState::Disconnected(Error::ClientDisconnected),
+        ) {
+            ConnectionState::Connected(client) => Some(client),
+            _ => unreachable!(),
+        }
+    }
+
     /// Whether this is currently in [ConnectionStateType::Connecting].
     pub fn is_connecting(&self) -> bool {
         self.state_type() == ConnectionStateType::Connecting
@@ -205,6 +440,19 @@ impl<S: DeserializeOwned + Send + 'static> Connection<S> {
         }
     }
 
+    /// If this client is disconnected, returns the connection error.
+    /// Otherwise, returns `None`.
+    ///
+    /// Unlike [into_err](Self::into_err), this doesn't consume the connection,
+    /// so it's suitable for displaying the error while keeping the connection
+    /// around.
+    pub fn error(&self) -> Option<&Error> {
+        match &self.state {
+            ConnectionState::Disconnected(err) => Some(err),
+            _ => None,
+        }
+    }
+
     /// Converts this into an error that's owned by the caller.
     ///
     /// If this is called when this isn't in an error state, it returns
@@ -265,9 +513,80 @@ impl<S: DeserializeOwned + 'static> fmt::Debug for ConnectionState<S> {
 
 /// The state of the Archipelago connection during the initial sequence of
 /// protocol handshakes.
-pub struct Connecting<S: DeserializeOwned + 'static>(
-    Pin<Box<dyn Future<Output = Result<Client<S>, Error>> + Send>>,
-);
+pub struct Connecting<S: DeserializeOwned + 'static> {
+    /// The future that resolves to the connected client.
+    future: Pin<Box<dyn Future<Output = Result<Client<S>, Error>> + Send>>,
+
+    /// The progress of [future] through the handshake.
+    progress: ConnectingProgress,
+}
+
+impl<S: DeserializeOwned + 'static> Connecting<S> {
+    /// The phase of the handshake the connection is currently in.
+    pub fn phase(&self) -> ConnectingPhase {
+        self.progress.get()
+    }
+}
+
+/// The phases an Archipelago connection moves through while it's being
+/// established, in order.
+///
+/// Connections don't necessarily pass through every phase. For example, if all
+/// data packages are already cached, the connection will skip
+/// [DataPackage](Self::DataPackage).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConnectingPhase {
+    /// The client is opening the WebSocket connection, including establishing
+    /// TCP and TLS connections.
+    OpeningSocket,
+
+    /// The client is waiting for the server to send information about the
+    /// room.
+    AwaitingRoomInfo,
+
+    /// The client is loading data packages from its local cache.
+    LoadingCachedDataPackages,
+
+    /// The client is downloading the data package for `game`, which wasn't
+    /// cached.
+    ///
+    /// Each game's data package is downloaded separately, so this can be used
+    /// to display a progress bar for rooms with many games. `index` is the
+    /// zero-based index of this download, and `total` is the number of data
+    /// packages being downloaded.
+    DataPackage {
+        game: Ustr,
+        index: usize,
+        total: usize,
+    },
+
+    /// The client is waiting for the server to accept its connection to a
+    /// slot.
+    AwaitingConnected,
+}
+
+/// A handle that the connection future uses to report its [ConnectingPhase]
+/// to the [Connecting] state.
+#[derive(Clone)]
+pub(crate) struct ConnectingProgress(Arc<Mutex<ConnectingPhase>>);
+
+impl Default for ConnectingProgress {
+    fn default() -> Self {
+        ConnectingProgress(Arc::new(Mutex::new(ConnectingPhase::OpeningSocket)))
+    }
+}
+
+impl ConnectingProgress {
+    /// Records that the connection has entered `phase`.
+    pub(crate) fn set(&self, phase: ConnectingPhase) {
+        *self.0.lock().unwrap() = phase;
+    }
+
+    /// Returns the phase the connection is currently in.
+    pub(crate) fn get(&self) -> ConnectingPhase {
+        *self.0.lock().unwrap()
+    }
+}
 
 /// An enumeration of possible types of [ConnectionState]s, without any extra
 /// data attached. Unlike the full [ConnectionState], this implements [Copy] and
@@ -288,10 +607,33 @@ pub struct ConnectionStateTransition {
     pub new: ConnectionStateType,
 }
 
+/// The transport an Archipelago connection uses to communicate with the server.
+///
+/// Every connection uses the same non-blocking socket, so the only thing that
+/// varies between them is whether and how they're encrypted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// An unencrypted `ws://` connection.
+    Plain,
+
+    /// A `wss://` connection encrypted using the `rustls` crate.
+    Rustls,
+
+    /// A `wss://` connection encrypted using the `native-tls` crate.
+    NativeTls,
+}
+
+impl Transport {
+    /// Whether this transport is encrypted using TLS.
+    pub fn is_tls(&self) -> bool {
+        !matches!(self, Transport::Plain)
+    }
+}
+
 /// If [future] is complete, returns its value. Otherwise, returns `None`.
 ///
 /// If this returns a value, `future` must not be polled again afterwards.
-fn try_future<T, F: FutureExt<Output = T> + Unpin>(future: &mut F) -> Option<T> {
+pub(crate) fn try_future<T, F: FutureExt<Output = T> + Unpin>(future: &mut F) -> Option<T> {
     let mut context = Context::from_waker(Waker::noop());
     match future.poll(&mut context) {
         Poll::Ready(value) => Some(value),
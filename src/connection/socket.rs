@@ -11,7 +11,6 @@ use native_tls::{HandshakeError as TlsHandshakeError, TlsConnector, TlsStream};
 use rustls::{
     ClientConfig, ClientConnection, KeyLogFile, OtherError, RootCertStore, pki_types::ServerName,
 };
-use serde::de::DeserializeOwned;
 use smol::{Async, net::TcpStream as AsyncTcpStream};
 use tungstenite::HandshakeError as WsHandshakeError;
 use tungstenite::client::IntoClientRequest;
@@ -19,10 +18,12 @@ use tungstenite::client::IntoClientRequest;
 use tungstenite::error::TlsError;
 use tungstenite::error::UrlError;
 use tungstenite::handshake::client::ClientHandshake;
+use tungstenite::http::HeaderValue;
 use tungstenite::stream::{MaybeTlsStream, Mode};
 use tungstenite::{Message, WebSocket};
 
-use crate::error::{Error, ProtocolError};
+use crate::Transport;
+use crate::error::{DnsFailure, Error, ProtocolError};
 use crate::protocol::{ClientMessage, ServerMessage};
 
 /// The default Archipelago port, used for localhost and potentially other
@@ -31,7 +32,7 @@ const DEFAULT_PORT: u16 = 38281;
 
 /// A WebSocket wrapper that receives Archipelago protocol messages from the
 /// server and decodes them.
-pub(crate) struct Socket<S: DeserializeOwned + 'static> {
+pub(crate) struct Socket {
     /// The async wrapper for the TCP stream. We use this to determine when it's
     /// readable and writable.
     async_stream: Arc<Async<SyncTcpStream>>,
@@ -41,10 +42,10 @@ pub(crate) struct Socket<S: DeserializeOwned + 'static> {
 
     /// The buffer of messages that have yet to be returned, in cases where the
     /// server sends multiple messages at a time.
-    messages: VecDeque<Result<ServerMessage<S>, Error>>,
+    messages: VecDeque<Result<ServerMessage, Error>>,
 }
 
-impl<S: DeserializeOwned + 'static> Socket<S> {
+impl Socket {
     /// Begins the process of establishing a WebSocket connection using the
     /// given [request] (which may be passed as a simple `ws://` or `wss://`
     /// URL).
@@ -54,9 +55,19 @@ impl<S: DeserializeOwned + 'static> Socket<S> {
     /// [Socket].
     pub(crate) async fn connect(
         request: impl IntoClientRequest,
+        subprotocol: Option<&str>,
         #[cfg(feature = "rustls")] rustls_config: Option<Arc<ClientConfig>>,
     ) -> Result<Self, Error> {
-        let request = request.into_client_request()?;
+        let mut request = request.into_client_request()?;
+        if let Some(subprotocol) = subprotocol {
+            // Tungstenite verifies that the server's response agrees with this
+            // during the handshake.
+            request.headers_mut().insert(
+                "Sec-WebSocket-Protocol",
+                HeaderValue::from_str(subprotocol)
+                    .map_err(|err| tungstenite::Error::HttpFormat(err.into()))?,
+            );
+        }
         let domain = request
             .uri()
             .host()
@@ -141,8 +152,31 @@ impl<S: DeserializeOwned + 'static> Socket<S> {
         domain: &str,
         port: u16,
     ) -> Result<(SyncTcpStream, Arc<Async<SyncTcpStream>>), Error> {
+        debug!("Resolving {domain}:{port}...");
+        // Resolve the address separately so that DNS failures can be reported
+        // distinctly from failures to connect to a resolved address.
+        let addrs = match smol::net::resolve((domain, port)).await {
+            Ok(addrs) if !addrs.is_empty() => addrs,
+            Ok(_) => {
+                return Err(DnsFailure::into_error(
+                    io::ErrorKind::NotFound,
+                    format!("{domain}:{port}"),
+                    "no addresses found",
+                )
+                .into());
+            }
+            Err(err) => {
+                return Err(DnsFailure::into_error(
+                    err.kind(),
+                    format!("{domain}:{port}"),
+                    err.to_string(),
+                )
+                .into());
+            }
+        };
+
         debug!("Establishing TCP connection to {domain}:{port}...");
-        let stream = match AsyncTcpStream::connect(format!("{domain}:{port}")).await {
+        let stream = match AsyncTcpStream::connect(addrs.as_slice()).await {
             Ok(stream) => stream,
             Err(err) => {
                 // Normalize OS errors into tungstenite's error wrapper.
@@ -287,11 +321,23 @@ impl<S: DeserializeOwned + 'static> Socket<S> {
     /// Archipelago or WebSocket levels. There's no guarantee that it will
     /// contain only one error. This automatically handles
     /// [io::ErrorKind::WouldBlock].
+    ///
+    /// If `limit` is passed, this returns at most that many results. Any
+    /// others remain queued and will be returned by later calls. Frames are
+    /// only decoded until `limit` results are available, so the work done by
+    /// each call is bounded even if the server has sent a large backlog.
     pub(crate) fn recv_all(
         &mut self,
-    ) -> impl IntoIterator<Item = Result<ServerMessage<S>, Error>> + use<S> {
-        self.read_inner();
-        mem::take(&mut self.messages)
+        limit: Option<usize>,
+    ) -> impl IntoIterator<Item = Result<ServerMessage, Error>> + use<> {
+        self.read_inner(limit);
+        match limit {
+            Some(limit) if limit < self.messages.len() => {
+                let rest = self.messages.split_off(limit);
+                mem::replace(&mut self.messages, rest)
+            }
+            _ => mem::take(&mut self.messages),
+        }
     }
 
     /// Returns the next result from this socket, if one is available. Returns
@@ -300,14 +346,14 @@ impl<S: DeserializeOwned + 'static> Socket<S> {
     ///
     /// This returns errors interleaved with messages in the order they were
     /// encountered. It automatically handles [io::ErrorKind::WouldBlock].
-    pub(crate) fn try_recv(&mut self) -> Option<Result<ServerMessage<S>, Error>> {
-        self.read_inner();
+    pub(crate) fn try_recv(&mut self) -> Option<Result<ServerMessage, Error>> {
+        self.read_inner(None);
         self.messages.pop_front()
     }
 
     /// Like [try_recv], but returns a Future that only resolves once a result
     /// is available.
-    pub(crate) async fn recv_async(&mut self) -> Result<ServerMessage<S>, Error> {
+    pub(crate) async fn recv_async(&mut self) -> Result<ServerMessage, Error> {
         loop {
             match self.try_recv() {
                 Some(result) => return result,
@@ -316,20 +362,30 @@ impl<S: DeserializeOwned + 'static> Socket<S> {
         }
     }
 
+    /// Returns a Future that resolves once the underlying stream has new data
+    /// to read.
+    pub(crate) async fn readable(&self) -> Result<(), Error> {
+        Ok(self.async_stream.readable().await?)
+    }
+
     /// Processes any queued messages in [Self::inner] and adds them to
     /// [Self::messages].
     ///
+    /// If `limit` is passed, this stops decoding frames once [Self::messages]
+    /// contains at least that many results. Any remaining frames are left in
+    /// [Self::inner] for later calls.
+    ///
     /// This can't fail because any errors it encounters will be put in
     /// [Self::messages] rather than returned directly.
-    fn read_inner(&mut self) {
-        // Always check the socket even if we already have messages in
-        // [Socket::messages] to ensure that we don't starve it and prevent it
-        // from responding to heartbeat pings.
-        while self.inner.can_read() {
+    fn read_inner(&mut self, limit: Option<usize>) {
+        // Otherwise, always check the socket even if we already have messages
+        // in [Socket::messages] to ensure that we don't starve it and prevent
+        // it from responding to heartbeat pings.
+        while self.inner.can_read() && limit.is_none_or(|limit| self.messages.len() < limit) {
             match self.inner.read() {
                 Ok(Message::Text(bytes)) => {
                     debug!("--> {bytes}");
-                    match serde_json::from_str::<Vec<ServerMessage<S>>>(&bytes) {
+                    match serde_json::from_str::<Vec<ServerMessage>>(&bytes) {
                         Ok(messages) => self.messages.extend(messages.into_iter().map(Ok)),
                         Err(err) => self.messages.push_back(Err(ProtocolError::Deserialize {
                             json: bytes.to_string(),
@@ -344,9 +400,15 @@ impl<S: DeserializeOwned + 'static> Socket<S> {
                         .push_back(Err(ProtocolError::BinaryMessage(bytes.to_vec()).into()));
                 }
 
-                Ok(Message::Close(_)) => {
-                    debug!("--> [closed]");
-                    let err = Error::WebSocket(tungstenite::Error::ConnectionClosed);
+                Ok(Message::Close(frame)) => {
+                    debug!("--> [closed] {frame:?}");
+                    let err = match frame {
+                        Some(frame) => Error::ServerClosed {
+                            code: frame.code.into(),
+                            reason: frame.reason.to_string(),
+                        },
+                        None => Error::WebSocket(tungstenite::Error::ConnectionClosed),
+                    };
                     self.messages.push_back(Err(err));
                     break;
                 }
@@ -372,10 +434,29 @@ impl<S: DeserializeOwned + 'static> Socket<S> {
         }
     }
 
+    /// Returns the transport this socket is using to communicate with the
+    /// server.
+    pub(crate) fn transport(&self) -> Transport {
+        match self.inner.get_ref() {
+            MaybeTlsStream::Plain(_) => Transport::Plain,
+            #[cfg(feature = "rustls")]
+            MaybeTlsStream::Rustls(_) => Transport::Rustls,
+            #[cfg(feature = "native-tls")]
+            MaybeTlsStream::NativeTls(_) => Transport::NativeTls,
+            _ => unreachable!("Socket only creates streams for enabled TLS features"),
+        }
+    }
+
     /// Sends [message] to the server.
     pub(crate) fn send(&mut self, message: ClientMessage) -> Result<(), Error> {
+        self.send_all(&[message])
+    }
+
+    /// Sends all of [messages] to the server in a single WebSocket frame. The
+    /// server processes them in order.
+    pub(crate) fn send_all(&mut self, messages: &[ClientMessage]) -> Result<(), Error> {
         self.inner
-            .send(Message::Text(match serde_json::to_string(&[&message]) {
+            .send(Message::Text(match serde_json::to_string(messages) {
                 Ok(text) => {
                     debug!("<-- {text}");
                     text.into()
@@ -386,3 +467,54 @@ impl<S: DeserializeOwned + 'static> Socket<S> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn recv_all_stops_decoding_at_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (sent, wait_for_sent) = mpsc::channel();
+        let (done, wait_for_done) = mpsc::channel::<()>();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept(stream).unwrap();
+            for i in 0..3 {
+                socket
+                    .send(Message::text(format!(
+                        r#"[{{"cmd": "Print", "text": "{i}"}}]"#
+                    )))
+                    .unwrap();
+            }
+            sent.send(()).unwrap();
+            // Keep the connection open until the client is done with it.
+            wait_for_done.recv().unwrap_err();
+        });
+
+        let mut socket = smol::block_on(Socket::connect(
+            url,
+            None,
+            #[cfg(feature = "rustls")]
+            None,
+        ))
+        .unwrap();
+        wait_for_sent.recv().unwrap();
+        // Give the frames time to arrive. They may already be buffered inside
+        // the WebSocket, so the stream won't necessarily become readable.
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(socket.recv_all(Some(1)).into_iter().count(), 1);
+        // The other frames shouldn't have been decoded yet.
+        assert!(socket.messages.is_empty());
+
+        assert_eq!(socket.recv_all(None).into_iter().count(), 2);
+        mem::drop(done);
+    }
+}
@@ -1,17 +1,27 @@
 #[cfg(feature = "rustls")]
 use std::sync::Arc;
 
+use std::time::Duration;
+
 use ustr::{Ustr, UstrSet};
 
 use crate::{Cache, protocol::ItemsHandlingFlags};
 
 /// A builder that defines options for
 /// [Connection::new](crate::Connection::new).
+#[derive(Clone)]
 pub struct ConnectionOptions {
     pub(crate) password: Option<String>,
     pub(crate) item_handling: ItemHandling,
     pub(crate) tags: UstrSet,
     pub(crate) cache: Option<Cache>,
+    pub(crate) no_cache: bool,
+    pub(crate) max_messages_per_update: Option<usize>,
+    pub(crate) bounce_filter: Option<UstrSet>,
+    pub(crate) death_link_dedup: Option<Duration>,
+    pub(crate) skip_items_before: Option<usize>,
+    pub(crate) subprotocol: Option<String>,
+    pub(crate) dry_run: bool,
     #[cfg(feature = "rustls")]
     pub(crate) rustls_config: Option<Arc<rustls::ClientConfig>>,
 }
@@ -24,6 +34,13 @@ impl ConnectionOptions {
             item_handling: Default::default(),
             tags: Default::default(),
             cache: None,
+            no_cache: false,
+            max_messages_per_update: None,
+            bounce_filter: None,
+            death_link_dedup: None,
+            skip_items_before: None,
+            subprotocol: None,
+            dry_run: false,
             #[cfg(feature = "rustls")]
             rustls_config: None,
         }
@@ -53,8 +70,115 @@ impl ConnectionOptions {
     /// Specify where cached data should be stored.
     ///
     /// By default, this will write to Archipelago's shared cache directory.
+    /// This overrides any previous call to [no_cache](Self::no_cache).
     pub fn cache(mut self, cache: Cache) -> Self {
         self.cache = Some(cache);
+        self.no_cache = false;
+        self
+    }
+
+    /// Disables caching entirely. Every data package will be downloaded from
+    /// the server and none will be written to disk.
+    ///
+    /// This is mostly useful for tests and CI environments that shouldn't
+    /// touch the user's cache directory.
+    pub fn no_cache(mut self) -> Self {
+        self.cache = None;
+        self.no_cache = true;
+        self
+    }
+
+    /// Sets the maximum number of server messages that a single call to
+    /// [Client::update](crate::Client::update) or
+    /// [Connection::update](crate::Connection::update) will process. Any
+    /// additional messages are left for subsequent calls.
+    ///
+    /// This can keep a single update from stalling a frame when the server
+    /// sends a large burst of messages at once, such as when re-sending a long
+    /// game's entire inventory.
+    ///
+    /// By default, there's no limit. Passing 0 also removes the limit, since
+    /// otherwise updates could never make progress.
+    pub fn max_messages_per_update(mut self, max: usize) -> Self {
+        self.max_messages_per_update = Some(max).filter(|max| *max != 0);
+        self
+    }
+
+    /// Only emit [Event::Bounce](crate::Event::Bounce) for generic bounces
+    /// that have at least one of `tags`. Other generic bounces are silently
+    /// dropped.
+    ///
+    /// This is useful for clients that use bounces for their own protocols and
+    /// don't want to handle bounces meant for other clients. It doesn't affect
+    /// [Event::DeathLink](crate::Event::DeathLink).
+    ///
+    /// By default, all generic bounces are emitted.
+    pub fn bounce_filter(mut self, tags: impl IntoIterator<Item: Into<Ustr>>) -> Self {
+        self.bounce_filter = Some(tags.into_iter().map(|t| t.into()).collect());
+        self
+    }
+
+    /// Suppresses incoming [Event::DeathLink](crate::Event::DeathLink)s that
+    /// arrive within `window` of the last death link that was emitted.
+    ///
+    /// This keeps a burst of death links (for example, from several players
+    /// dying at once) from killing the player repeatedly. Only the first death
+    /// link in each burst is emitted.
+    ///
+    /// By default, all death links are emitted.
+    pub fn death_link_dedup(mut self, window: Duration) -> Self {
+        self.death_link_dedup = Some(window);
+        self
+    }
+
+    /// Treats the first `last_received_index` items the server sends upon
+    /// connecting as already processed. This should be the value of
+    /// [Client::last_received_index](crate::Client::last_received_index) that
+    /// the client stored the last time it was connected.
+    ///
+    /// The server always re-sends the player's entire inventory when a client
+    /// connects, and the protocol has no way to ask it not to. With this
+    /// option, the client still stores every item in
+    /// [Client::received_items](crate::Client::received_items), but the
+    /// initial [Event::ReceivedItems](crate::Event::ReceivedItems) starts at
+    /// `last_received_index` rather than 0 (and is omitted entirely if there
+    /// are no new items). If the server sends fewer items than
+    /// `last_received_index`, which usually means the save file belongs to a
+    /// different session, all items are reported as usual.
+    pub fn skip_initial_item_replay(mut self, last_received_index: usize) -> Self {
+        self.skip_items_before = Some(last_received_index);
+        self
+    }
+
+    /// Requests the given WebSocket subprotocol when connecting, for
+    /// deployments that require one.
+    ///
+    /// If the server doesn't agree to use this subprotocol, connecting fails
+    /// with [Error::WebSocket](crate::Error::WebSocket). By default, no
+    /// subprotocol is requested.
+    pub fn subprotocol(mut self, name: impl Into<String>) -> Self {
+        self.subprotocol = Some(name.into());
+        self
+    }
+
+    /// Puts the client in dry-run mode, where requests that would modify the
+    /// server's state are logged at info level instead of being sent.
+    ///
+    /// This affects requests like [Client::mark_checked], [Client::say],
+    /// [Client::bounce], and [Client::set]. Requests that only read from the
+    /// server still work, although [Client::scout_locations] never creates
+    /// hints. Suppressed requests still update the client's local state as
+    /// though they had been sent, so for example [Client::mark_checked] still
+    /// marks locations as checked locally. This is useful for testing client
+    /// logic against a live multiworld without affecting it.
+    ///
+    /// [Client::mark_checked]: crate::Client::mark_checked
+    /// [Client::say]: crate::Client::say
+    /// [Client::bounce]: crate::Client::bounce
+    /// [Client::set]: crate::Client::set
+    /// [Client::scout_locations]: crate::Client::scout_locations
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
         self
     }
 
@@ -78,6 +202,7 @@ impl Default for ConnectionOptions {
 }
 
 /// Possible options for handling items.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ItemHandling {
     /// No items are sent to this client.
     None,
@@ -1,17 +1,21 @@
 mod game;
+mod hint;
 mod item;
 mod located_item;
 mod location;
 mod player;
 mod print;
 mod received_item;
+mod scout_summary;
 mod version;
 
 pub use game::*;
+pub use hint::*;
 pub use item::*;
 pub use located_item::*;
 pub use location::*;
 pub use player::*;
 pub use print::*;
 pub use received_item::*;
+pub use scout_summary::*;
 pub use version::*;
@@ -53,6 +53,10 @@ struct DataPackageGame {
     // These map values are indices into [locations].
     locations_by_id: HashMap<i64, usize>,
     locations_by_name: UstrMap<usize>,
+
+    // These map group names to the names of their members.
+    item_groups: UstrMap<Vec<Ustr>>,
+    location_groups: UstrMap<Vec<Ustr>>,
 }
 
 impl Game {
@@ -81,6 +85,8 @@ impl Game {
             items_by_name,
             locations_by_id,
             locations_by_name,
+            item_groups: Default::default(),
+            location_groups: Default::default(),
         }))
     }
 
@@ -93,6 +99,23 @@ impl Game {
     pub(crate) fn hydrate(name: Ustr, network: GameData) -> Game {
         // TODO: Is it really worth the hassle of supporting indexing by name?
         // Consider dropping that and just storing these as `HashMap<i64, _>`s.
+
+        // Names can't collide because they're map keys, but a malformed apworld
+        // can assign the same ID to multiple names. Report that so apworld
+        // authors can notice. Lookups by ID will return one of the names.
+        for (id, names) in duplicate_ids(&network.item_name_to_id) {
+            log::warn!(
+                "{name} data package has duplicate item ID {id} for {}",
+                quoted_list(&names)
+            );
+        }
+        for (id, names) in duplicate_ids(&network.location_name_to_id) {
+            log::warn!(
+                "{name} data package has duplicate location ID {id} for {}",
+                quoted_list(&names)
+            );
+        }
+
         let mut items = Vec::with_capacity(network.item_name_to_id.len());
         let mut items_by_id = HashMap::with_capacity(network.item_name_to_id.len());
         let mut items_by_name =
@@ -124,6 +147,8 @@ impl Game {
             items_by_name,
             locations_by_id,
             locations_by_name,
+            item_groups: network.item_name_groups,
+            location_groups: network.location_name_groups,
         }))
     }
 
@@ -167,6 +192,49 @@ impl Game {
         }
     }
 
+    /// The names and IDs of all the items in this game, like the data package's
+    /// `item_name_to_id` map.
+    ///
+    /// For games without data packages, this is always empty.
+    pub fn item_names(&self) -> impl Iter<(&str, i64)> {
+        self.items().map(|item| (item.name().as_str(), item.id()))
+    }
+
+    /// The names and IDs of all the locations in this game, like the data
+    /// package's `location_name_to_id` map.
+    ///
+    /// For games without data packages, this is always empty.
+    pub fn location_names(&self) -> impl Iter<(&str, i64)> {
+        self.locations()
+            .map(|location| (location.name().as_str(), location.id()))
+    }
+
+    /// The names of this game's item groups, along with the names of the items
+    /// in each group.
+    ///
+    /// Newer Archipelago servers don't include groups in data packages, so
+    /// this is often empty. It's always empty for games without data packages.
+    pub fn item_groups(&self) -> impl Iter<(Ustr, &[Ustr])> {
+        match &self.0 {
+            GameType::NoDataPackage(_) => Default::default(),
+            GameType::DataPackage(game) => game.item_groups.iter(),
+        }
+        .map(|(name, members)| (*name, members.as_slice()))
+    }
+
+    /// The names of this game's location groups, along with the names of the
+    /// locations in each group.
+    ///
+    /// Newer Archipelago servers don't include groups in data packages, so
+    /// this is often empty. It's always empty for games without data packages.
+    pub fn location_groups(&self) -> impl Iter<(Ustr, &[Ustr])> {
+        match &self.0 {
+            GameType::NoDataPackage(_) => Default::default(),
+            GameType::DataPackage(game) => game.location_groups.iter(),
+        }
+        .map(|(name, members)| (*name, members.as_slice()))
+    }
+
     /// Whether this game defines an item with the given `id`.
     ///
     /// For games without data packages, this always returns true.
@@ -330,3 +398,136 @@ impl fmt::Debug for Game {
         }
     }
 }
+
+/// Returns each ID in `names_to_ids` that's assigned to more than one name,
+/// along with all the names it's assigned to.
+///
+/// Both the IDs and the names are sorted so that reports are deterministic.
+fn duplicate_ids(names_to_ids: &HashMap<Ustr, i64>) -> Vec<(i64, Vec<Ustr>)> {
+    let mut names_by_id = HashMap::<i64, Vec<Ustr>>::with_capacity(names_to_ids.len());
+    for (name, id) in names_to_ids {
+        names_by_id.entry(*id).or_default().push(*name);
+    }
+
+    let mut duplicates = names_by_id
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .map(|(id, mut names)| {
+            names.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            (id, names)
+        })
+        .collect::<Vec<_>>();
+    duplicates.sort_by_key(|(id, _)| *id);
+    duplicates
+}
+
+/// Formats `names` as a comma-separated list of quoted strings.
+fn quoted_list(names: &[Ustr]) -> String {
+    names
+        .iter()
+        .map(|name| format!("\"{name}\""))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use ustr::ustr;
+
+    use super::*;
+
+    /// Returns a game named "Game" with two items and two locations.
+    fn small_game() -> Game {
+        Game::hydrate(
+            ustr("Game"),
+            serde_json::from_value(serde_json::json!({
+                "item_name_to_id": {"Sword": 1, "Shield": 2},
+                "location_name_to_id": {"Chest": 10, "Cave": 11},
+                "checksum": "abc"
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn validated_lookups_reject_unknown_ids() {
+        let game = small_game();
+        assert_eq!(game.location(10).unwrap().name(), "Chest");
+        assert!(game.location(12).is_none());
+        assert!(game.location(-1).is_none());
+        assert!(!game.has_location(12));
+        assert_eq!(game.item(2).unwrap().name(), "Shield");
+        assert!(game.item(3).is_none());
+        assert!(!game.has_item(3));
+    }
+
+    #[test]
+    fn validated_lookups_without_data_package() {
+        // Without a data package, there's no way to tell which IDs are valid.
+        let game = Game::no_data_package(ustr("Game"));
+        assert_eq!(game.location(12).unwrap().id(), 12);
+        assert_eq!(game.item(3).unwrap().id(), 3);
+    }
+
+    #[test]
+    fn names() {
+        let game = small_game();
+        let mut items = game.item_names().collect::<Vec<_>>();
+        items.sort();
+        assert_eq!(items, [("Shield", 2), ("Sword", 1)]);
+
+        let mut locations = game.location_names().collect::<Vec<_>>();
+        locations.sort();
+        assert_eq!(locations, [("Cave", 11), ("Chest", 10)]);
+    }
+
+    #[test]
+    fn names_without_data_package() {
+        let game = Game::no_data_package(ustr("Game"));
+        assert_eq!(game.item_names().len(), 0);
+        assert_eq!(game.location_names().len(), 0);
+    }
+
+    #[test]
+    fn duplicate_ids_reports_collisions() {
+        let names_to_ids = HashMap::from_iter([
+            (ustr("Sword"), 1),
+            (ustr("Shield"), 2),
+            (ustr("Old Sword"), 1),
+            (ustr("Potion"), 3),
+            (ustr("Elixir"), 3),
+            (ustr("Tonic"), 3),
+        ]);
+        assert_eq!(
+            duplicate_ids(&names_to_ids),
+            [
+                (1, vec![ustr("Old Sword"), ustr("Sword")]),
+                (3, vec![ustr("Elixir"), ustr("Potion"), ustr("Tonic")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_ids_without_collisions() {
+        let names_to_ids = HashMap::from_iter([(ustr("Sword"), 1), (ustr("Shield"), 2)]);
+        assert!(duplicate_ids(&names_to_ids).is_empty());
+    }
+
+    #[test]
+    fn hydrate_keeps_colliding_names() {
+        let game = Game::hydrate(
+            ustr("Game"),
+            serde_json::from_value(serde_json::json!({
+                "item_name_to_id": {"Sword": 1, "Old Sword": 1},
+                "location_name_to_id": {"Chest": 10},
+                "checksum": "abc"
+            }))
+            .unwrap(),
+        );
+        // Both names are still usable, even though only one of them can be
+        // returned for the ID.
+        assert_eq!(game.item_by_name("Sword").unwrap().id(), 1);
+        assert_eq!(game.item_by_name("Old Sword").unwrap().id(), 1);
+        assert!(["Sword", "Old Sword"].contains(&game.item(1).unwrap().name().as_str()));
+    }
+}
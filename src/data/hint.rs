@@ -0,0 +1,140 @@
+use serde::de::DeserializeOwned;
+
+use crate::protocol::{HintStatus, NetworkHint, NetworkItem};
+use crate::{Client, Error, LocatedItem, ProtocolError};
+
+/// A hint indicating where an item is located.
+#[derive(Debug, Clone)]
+pub struct Hint {
+    item: LocatedItem,
+    found: bool,
+    entrance: Option<String>,
+    status: HintStatus,
+}
+
+impl Hint {
+    /// Creates a fully-hydrated [Hint] from a [NetworkHint].
+    pub(crate) fn hydrate<S: DeserializeOwned>(
+        network: NetworkHint,
+        client: &Client<S>,
+    ) -> Result<Hint, Error> {
+        let sender = client.teammate_arc(network.finding_player)?;
+        let receiver = client.teammate_arc(network.receiving_player)?;
+        let item = LocatedItem::hydrate(
+            NetworkItem {
+                item: network.item,
+                location: network.location,
+                player: network.finding_player,
+                flags: network.item_flags,
+            },
+            sender,
+            receiver,
+            client,
+        )?;
+        Ok(Hint {
+            item,
+            found: network.found,
+            entrance: Some(network.entrance).filter(|e| !e.is_empty()),
+            status: network.status,
+        })
+    }
+
+    /// Parses the value of a `_read_hints_{team}_{slot}` data storage key into
+    /// a list of hints.
+    ///
+    /// This is mostly useful for clients that [watch](Client::watch) hint keys
+    /// directly. Otherwise, use [Client::get_hints_for_slot].
+    pub fn from_storage<S: DeserializeOwned>(
+        value: serde_json::Value,
+        client: &Client<S>,
+    ) -> Result<Vec<Hint>, Error> {
+        parse_storage(value)?
+            .into_iter()
+            .map(|network| Hint::hydrate(network, client))
+            .collect()
+    }
+
+    /// The hinted item, including where it's located and who will receive it.
+    pub fn item(&self) -> &LocatedItem {
+        &self.item
+    }
+
+    /// Whether the hinted item has already been found.
+    pub fn is_found(&self) -> bool {
+        self.found
+    }
+
+    /// The entrance through which the hinted location is accessed, for
+    /// entrance-randomized games that provide that information.
+    pub fn entrance(&self) -> Option<&str> {
+        self.entrance.as_deref()
+    }
+
+    /// The hint's status, indicating how much the receiving player wants the
+    /// item.
+    pub fn status(&self) -> HintStatus {
+        self.status
+    }
+}
+
+/// Parses the value of a `_read_hints_{team}_{slot}` data storage key into a
+/// list of [NetworkHint]s.
+fn parse_storage(value: serde_json::Value) -> Result<Vec<NetworkHint>, Error> {
+    if value.is_null() {
+        // The server returns null for slots that have never had hints.
+        return Ok(Vec::new());
+    }
+
+    let json = value.to_string();
+    serde_json::from_value::<Vec<NetworkHint>>(value)
+        .map_err(|error| ProtocolError::Deserialize { json, error }.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_storage_null() {
+        assert!(parse_storage(serde_json::Value::Null).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_storage_statuses() {
+        let hints = parse_storage(json!([
+            {
+                "class": "Hint",
+                "receiving_player": 1,
+                "finding_player": 2,
+                "location": 100,
+                "item": 200,
+                "found": true,
+                "entrance": "",
+                "item_flags": 1,
+                "status": 40
+            },
+            {
+                "class": "Hint",
+                "receiving_player": 2,
+                "finding_player": 1,
+                "location": 101,
+                "item": 201,
+                "found": false,
+                "entrance": "Secret Door",
+                "item_flags": 0,
+                "status": 30
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(hints.len(), 2);
+        assert!(hints[0].found);
+        assert_eq!(hints[0].status, HintStatus::Found);
+        assert_eq!(hints[0].location, 100);
+        assert!(!hints[1].found);
+        assert_eq!(hints[1].status, HintStatus::Priority);
+        assert_eq!(hints[1].entrance, "Secret Door");
+    }
+}
@@ -4,6 +4,8 @@ use std::hash::{Hash, Hasher};
 
 use ustr::Ustr;
 
+use crate::ARCHIPELAGO_NAME;
+
 /// An Archipelago item for some player's game.
 #[derive(Debug, Clone, Copy)]
 pub struct Item {
@@ -32,6 +34,18 @@ impl Item {
     pub fn game(&self) -> Ustr {
         self.game
     }
+
+    /// The item's name prefixed with its game, such as `"Dark Souls III: Estus
+    /// Shard"`. This is useful for UIs that show items from many worlds.
+    ///
+    /// Items in the Archipelago pseudo-game are returned without a prefix.
+    pub fn qualified_name(&self) -> String {
+        if self.game == *ARCHIPELAGO_NAME {
+            self.name.to_string()
+        } else {
+            format!("{}: {}", self.game, self.name)
+        }
+    }
 }
 
 impl fmt::Display for Item {
@@ -55,6 +69,11 @@ impl Hash for Item {
 }
 
 /// A trait for values that can be interpreted as Archipelago items.
+///
+/// This is implemented for raw `i64` IDs, which aren't validated until they're
+/// used. To check that an ID refers to a real item up front, convert it using
+/// [Game::item](crate::Game::item) instead, which returns `None` for IDs
+/// that don't exist in that game's data package.
 pub trait AsItemId {
     /// Returns this value as an Archipelago item ID.
     fn as_item_id(&self) -> i64;
@@ -77,3 +96,22 @@ impl AsItemId for i64 {
         *self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ustr::ustr;
+
+    use super::*;
+
+    #[test]
+    fn qualified_name() {
+        let item = Item::new(1, ustr("Estus Shard"), ustr("Dark Souls III"));
+        assert_eq!(item.qualified_name(), "Dark Souls III: Estus Shard");
+    }
+
+    #[test]
+    fn qualified_name_archipelago() {
+        let item = Item::new(-1, ustr("Nothing"), *ARCHIPELAGO_NAME);
+        assert_eq!(item.qualified_name(), "Nothing");
+    }
+}
@@ -75,6 +75,15 @@ impl LocatedItem {
         self.receiver.as_ref()
     }
 
+    /// The raw flags the server sent for this item.
+    ///
+    /// In most cases [is_progression](Self::is_progression),
+    /// [is_useful](Self::is_useful), and [is_trap](Self::is_trap) are more
+    /// convenient, but this is useful for passing the flags along verbatim.
+    pub fn flags(&self) -> NetworkItemFlags {
+        self.flags
+    }
+
     /// Whether this item can unblock logical advancement.
     pub fn is_progression(&self) -> bool {
         self.flags.contains(NetworkItemFlags::PROGRESSION)
@@ -91,6 +100,14 @@ impl LocatedItem {
     }
 }
 
+// This allows generic code to accept either a [LocatedItem] or a
+// [ReceivedItem](crate::ReceivedItem) via `AsRef<LocatedItem>`.
+impl AsRef<LocatedItem> for LocatedItem {
+    fn as_ref(&self) -> &LocatedItem {
+        self
+    }
+}
+
 impl fmt::Debug for LocatedItem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
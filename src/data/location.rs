@@ -74,6 +74,19 @@ impl Location {
     pub fn game(&self) -> Ustr {
         self.game
     }
+
+    /// This location's name prefixed with its game, such as `"Dark Souls III: Firelink
+    /// Shrine"`. This is useful for UIs that show locations from many worlds.
+    ///
+    /// Locations in the Archipelago pseudo-game are returned without a
+    /// prefix.
+    pub fn qualified_name(&self) -> String {
+        if self.game == *ARCHIPELAGO_NAME {
+            self.name.to_string()
+        } else {
+            format!("{}: {}", self.game, self.name)
+        }
+    }
 }
 
 impl fmt::Display for Location {
@@ -97,6 +110,11 @@ impl Hash for Location {
 }
 
 /// A trait for values that can be interpreted as Archipelago locations.
+///
+/// This is implemented for raw `i64` IDs, which aren't validated until they're
+/// used. To check that an ID refers to a real location up front, convert it using
+/// [Game::location](crate::Game::location) instead, which returns `None` for IDs
+/// that don't exist in that game's data package.
 pub trait AsLocationId {
     /// Returns this value as an Archipelago location ID.
     fn as_location_id(&self) -> i64;
@@ -119,3 +137,22 @@ impl AsLocationId for i64 {
         *self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ustr::ustr;
+
+    use super::*;
+
+    #[test]
+    fn qualified_name() {
+        let location = Location::new(1, ustr("Firelink Shrine"), ustr("Dark Souls III"));
+        assert_eq!(location.qualified_name(), "Dark Souls III: Firelink Shrine");
+    }
+
+    #[test]
+    fn qualified_name_well_known() {
+        assert_eq!(Location::cheat_console().qualified_name(), "Cheat Console");
+        assert_eq!(Location::server().qualified_name(), "Server");
+    }
+}
@@ -1,5 +1,5 @@
 use serde::de::DeserializeOwned;
-use std::{fmt, sync::Arc};
+use std::{fmt, mem, sync::Arc};
 
 use crate::protocol::{NetworkItemFlags, NetworkPrint, NetworkText};
 use crate::{Client, Error, Item, LocatedItem, Location, Player};
@@ -57,6 +57,9 @@ pub enum Print {
 
     /// The client has triggered a tutorial message, such as when first
     /// connecting.
+    ///
+    /// This is onboarding guidance for the player rather than a chat message,
+    /// so clients may want to display it separately.
     Tutorial { data: Vec<RichText> },
 
     /// A player changed their tags.
@@ -231,6 +234,12 @@ impl Print {
         text.into()
     }
 
+    /// Like [message](Self::message), but parses ANSI color codes in `text`
+    /// into [RichText::Color]s. See [RichText::parse_ansi].
+    pub fn ansi_message(text: &str) -> Print {
+        RichText::parse_ansi(text).into()
+    }
+
     /// Returns the data field for any Print.
     pub fn data(&self) -> &[RichText] {
         use Print::*;
@@ -253,6 +262,17 @@ impl Print {
             Unknown { data, .. } => data,
         }
     }
+
+    /// Returns the item this message refers to, if it's an
+    /// [ItemSend](Print::ItemSend), [ItemCheat](Print::ItemCheat), or
+    /// [Hint](Print::Hint) message.
+    pub fn item(&self) -> Option<&LocatedItem> {
+        use Print::*;
+        match self {
+            ItemSend { item, .. } | ItemCheat { item, .. } | Hint { item, .. } => Some(item),
+            _ => None,
+        }
+    }
 }
 
 impl From<String> for Print {
@@ -335,6 +355,77 @@ pub enum RichText {
 }
 
 impl RichText {
+    /// Parses ANSI SGR escape sequences (such as `"\x1b[31m"`) in `text` into
+    /// [RichText::Color]s, so that plain-text messages can be rendered the same
+    /// way as structured ones.
+    ///
+    /// Only bold, underline, and the eight standard foreground and background
+    /// colors are supported, since those are the only colors [TextColor] can
+    /// represent. If multiple attributes are active at once, the most recent
+    /// one wins. Text without any active attributes, as well as any escape
+    /// sequences that can't be parsed, are returned as [RichText::Text].
+    pub fn parse_ansi(text: &str) -> Vec<RichText> {
+        let mut segments = Vec::new();
+        let mut color = None;
+        let mut current = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("\x1b[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find(|c: char| !c.is_ascii_digit() && c != ';') else {
+                break;
+            };
+            if !after[end..].starts_with('m') {
+                // Not an SGR sequence, so leave it as-is.
+                current.push_str(&rest[..start + 2]);
+                rest = after;
+                continue;
+            }
+
+            current.push_str(&rest[..start]);
+            let new_color = after[..end]
+                .split(';')
+                .fold(color.clone(), |color, code| match code {
+                    "" | "0" => None,
+                    "1" => Some(TextColor::Bold),
+                    "4" => Some(TextColor::Underline),
+                    "30" => Some(TextColor::Black),
+                    "31" => Some(TextColor::Red),
+                    "32" => Some(TextColor::Green),
+                    "33" => Some(TextColor::Yellow),
+                    "34" => Some(TextColor::Blue),
+                    "35" => Some(TextColor::Magenta),
+                    "36" => Some(TextColor::Cyan),
+                    "37" => Some(TextColor::White),
+                    "40" => Some(TextColor::BlackBg),
+                    "41" => Some(TextColor::RedBg),
+                    "42" => Some(TextColor::GreenBg),
+                    "43" => Some(TextColor::YellowBg),
+                    "44" => Some(TextColor::BlueBg),
+                    "45" => Some(TextColor::MagentaBg),
+                    "46" => Some(TextColor::CyanBg),
+                    "47" => Some(TextColor::WhiteBg),
+                    _ => color,
+                });
+            Self::push_ansi_segment(&mut segments, mem::take(&mut current), color);
+            color = new_color;
+            rest = &after[end + 1..];
+        }
+        current.push_str(rest);
+        Self::push_ansi_segment(&mut segments, current, color);
+        segments
+    }
+
+    /// Adds `text` to `segments` with the given `color`, if it's non-empty.
+    fn push_ansi_segment(segments: &mut Vec<RichText>, text: String, color: Option<TextColor>) {
+        if text.is_empty() {
+            return;
+        }
+        segments.push(match color {
+            Some(color) => RichText::Color { text, color },
+            None => RichText::Text(text),
+        });
+    }
+
     /// Converts [NetworkText]s in [vec] to [RichText]s.
     fn hydrate_vec<S: DeserializeOwned>(
         vec: Vec<NetworkText>,
@@ -400,3 +491,71 @@ impl fmt::Display for RichText {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `text` with [RichText::parse_ansi] and returns each segment's
+    /// text along with the debug representation of its color, or `"Text"` for
+    /// uncolored segments.
+    fn parse_ansi(text: &str) -> Vec<(String, String)> {
+        RichText::parse_ansi(text)
+            .into_iter()
+            .map(|segment| match segment {
+                RichText::Color { text, color } => (text, format!("{color:?}")),
+                RichText::Text(text) => (text, "Text".into()),
+                segment => panic!("unexpected segment {segment:?}"),
+            })
+            .collect()
+    }
+
+    /// Returns an owned version of `segments` for comparison with
+    /// [parse_ansi].
+    fn segments(segments: &[(&str, &str)]) -> Vec<(String, String)> {
+        segments
+            .iter()
+            .map(|(text, color)| (text.to_string(), color.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_ansi_plain() {
+        assert_eq!(parse_ansi("hello"), segments(&[("hello", "Text")]));
+        assert_eq!(parse_ansi(""), segments(&[]));
+    }
+
+    #[test]
+    fn parse_ansi_colors() {
+        assert_eq!(
+            parse_ansi("a \x1b[31mred\x1b[0m b \x1b[44mblue\x1b[m"),
+            segments(&[
+                ("a ", "Text"),
+                ("red", "Red"),
+                (" b ", "Text"),
+                ("blue", "BlueBg"),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_ansi_multiple_codes() {
+        // The last recognized code wins, and unrecognized codes are ignored.
+        assert_eq!(
+            parse_ansi("\x1b[1;32;99mgreen"),
+            segments(&[("green", "Green")])
+        );
+        assert_eq!(
+            parse_ansi("\x1b[4mx\x1b[99my"),
+            segments(&[("x", "Underline"), ("y", "Underline")])
+        );
+    }
+
+    #[test]
+    fn parse_ansi_non_sgr() {
+        // Cursor movement isn't SGR, so it's left in the text.
+        assert_eq!(parse_ansi("a\x1b[2Kb"), segments(&[("a\x1b[2Kb", "Text")]));
+        // An unterminated sequence is left in the text as well.
+        assert_eq!(parse_ansi("a\x1b[31"), segments(&[("a\x1b[31", "Text")]));
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::{Item, LocatedItem, Location, Player};
+use crate::{Item, LocatedItem, Location, NetworkItemFlags, Player};
 
 /// An item that was received from the server.
 ///
@@ -55,6 +55,11 @@ impl ReceivedItem {
         self.item.receiver()
     }
 
+    /// The raw flags the server sent for this item.
+    pub fn flags(&self) -> NetworkItemFlags {
+        self.item.flags()
+    }
+
     /// Whether this item can unblock logical advancement.
     pub fn is_progression(&self) -> bool {
         self.item.is_progression()
@@ -83,7 +88,15 @@ impl From<ReceivedItem> for LocatedItem {
     }
 }
 
-impl AsRef<LocatedItem> for &ReceivedItem {
+impl From<&ReceivedItem> for LocatedItem {
+    fn from(value: &ReceivedItem) -> LocatedItem {
+        value.item.clone()
+    }
+}
+
+// This also covers `&ReceivedItem` through the standard library's blanket
+// implementation for references.
+impl AsRef<LocatedItem> for ReceivedItem {
     fn as_ref(&self) -> &LocatedItem {
         &self.item
     }
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use crate::{Iter, LocatedItem, Player};
+
+/// A summary of the items at a set of scouted locations, grouped by the player
+/// who will receive each one.
+///
+/// This is returned by [Client::scout_summary](crate::Client::scout_summary).
+#[derive(Debug, Clone)]
+pub struct ScoutSummary {
+    /// Groups of items that share a receiver, in the order in which each
+    /// receiver first appeared. Each group is guaranteed to be non-empty.
+    groups: Vec<Vec<LocatedItem>>,
+
+    progression: usize,
+    useful: usize,
+    traps: usize,
+}
+
+impl ScoutSummary {
+    /// Creates a summary of [items].
+    pub(crate) fn new(items: &[LocatedItem]) -> ScoutSummary {
+        let mut groups = Vec::<Vec<LocatedItem>>::new();
+        let mut indices = HashMap::<(u32, u32), usize>::new();
+        let mut progression = 0;
+        let mut useful = 0;
+        let mut traps = 0;
+        for item in items {
+            let receiver = item.receiver();
+            let index = *indices
+                .entry((receiver.team(), receiver.slot()))
+                .or_insert_with(|| {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                });
+            groups[index].push(item.clone());
+
+            if item.is_progression() {
+                progression += 1;
+            }
+            if item.is_useful() {
+                useful += 1;
+            }
+            if item.is_trap() {
+                traps += 1;
+            }
+        }
+
+        ScoutSummary {
+            groups,
+            progression,
+            useful,
+            traps,
+        }
+    }
+
+    /// Returns each player who will receive at least one of the scouted items,
+    /// along with the items they'll receive.
+    pub fn by_receiver(&self) -> impl Iter<(&Player, &[LocatedItem])> {
+        self.groups
+            .iter()
+            .map(|group| (group[0].receiver(), group.as_slice()))
+    }
+
+    /// Returns the scouted items that `player` will receive.
+    pub fn items_for(&self, player: &Player) -> &[LocatedItem] {
+        self.groups
+            .iter()
+            .find(|group| {
+                let receiver = group[0].receiver();
+                receiver.team() == player.team() && receiver.slot() == player.slot()
+            })
+            .map(|group| group.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The number of scouted items that can unblock logical advancement.
+    pub fn progression_count(&self) -> usize {
+        self.progression
+    }
+
+    /// The number of scouted items that are especially useful.
+    pub fn useful_count(&self) -> usize {
+        self.useful
+    }
+
+    /// The number of scouted items that are traps.
+    pub fn trap_count(&self) -> usize {
+        self.traps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use ustr::ustr;
+
+    use super::*;
+    use crate::Game;
+    use crate::protocol::{NetworkItem, NetworkItemFlags, NetworkPlayer, NetworkSlot, SlotType};
+
+    /// Returns a player in slot `slot` playing "Game".
+    fn player(slot: u32) -> Arc<Player> {
+        let name = ustr(&format!("Player{slot}"));
+        Arc::new(
+            Player::hydrate(
+                NetworkPlayer {
+                    team: 0,
+                    slot,
+                    alias: name.to_string(),
+                    name,
+                },
+                &NetworkSlot {
+                    name,
+                    game: ustr("Game"),
+                    r#type: SlotType::Player,
+                    group_members: Vec::new(),
+                },
+                &Default::default(),
+            )
+            .unwrap(),
+        )
+    }
+
+    /// Returns an item sent from `sender` to `receiver`.
+    fn item(
+        game: &Game,
+        sender: &Arc<Player>,
+        receiver: &Arc<Player>,
+        location: i64,
+        flags: NetworkItemFlags,
+    ) -> LocatedItem {
+        LocatedItem::hydrate_with_games(
+            NetworkItem {
+                item: 1,
+                location,
+                player: sender.slot(),
+                flags,
+            },
+            sender.clone(),
+            receiver.clone(),
+            game,
+            game,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn groups_and_counts() {
+        let game = Game::hydrate(
+            ustr("Game"),
+            serde_json::from_value(json!({
+                "item_name_to_id": {"Item": 1},
+                "location_name_to_id": {"A": 10, "B": 11, "C": 12, "D": 13},
+                "checksum": "abc",
+            }))
+            .unwrap(),
+        );
+        let (me, other, absent) = (player(1), player(2), player(3));
+        let summary = ScoutSummary::new(&[
+            item(&game, &me, &other, 10, NetworkItemFlags::PROGRESSION),
+            item(&game, &me, &me, 11, NetworkItemFlags::USEFUL),
+            item(&game, &me, &other, 12, NetworkItemFlags::TRAP),
+            item(
+                &game,
+                &me,
+                &me,
+                13,
+                NetworkItemFlags::PROGRESSION | NetworkItemFlags::USEFUL,
+            ),
+        ]);
+
+        let groups = summary
+            .by_receiver()
+            .map(|(player, items)| {
+                (
+                    player.slot(),
+                    items.iter().map(|i| i.location().id()).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(groups, [(2, vec![10, 12]), (1, vec![11, 13])]);
+
+        assert_eq!(summary.items_for(&me).len(), 2);
+        assert_eq!(summary.items_for(&other).len(), 2);
+        assert!(summary.items_for(&absent).is_empty());
+
+        assert_eq!(summary.progression_count(), 2);
+        assert_eq!(summary.useful_count(), 2);
+        assert_eq!(summary.trap_count(), 1);
+    }
+
+    #[test]
+    fn empty() {
+        let summary = ScoutSummary::new(&[]);
+        assert_eq!(summary.by_receiver().len(), 0);
+        assert_eq!(summary.progression_count(), 0);
+        assert_eq!(summary.useful_count(), 0);
+        assert_eq!(summary.trap_count(), 0);
+    }
+}
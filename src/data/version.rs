@@ -3,7 +3,7 @@ use std::fmt;
 use crate::protocol::NetworkVersion;
 
 /// A version of Archipelago, including the server and the generator.
-#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct Version {
     major: u16,
     minor: u16,
@@ -11,6 +11,15 @@ pub struct Version {
 }
 
 impl Version {
+    /// Creates a version from its components.
+    pub(crate) const fn new(major: u16, minor: u16, build: u16) -> Version {
+        Version {
+            major,
+            minor,
+            build,
+        }
+    }
+
     /// The major version number.
     pub fn major(&self) -> u16 {
         self.major
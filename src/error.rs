@@ -1,4 +1,7 @@
+use std::io;
+
 use thiserror::Error as ThisError;
+use tungstenite::error::UrlError;
 use ustr::{Ustr, UstrSet};
 
 use crate::LocatedItem;
@@ -17,6 +20,23 @@ pub enum Error {
     #[error("{0}")]
     Async(#[from] smol::io::Error),
 
+    /// The Archipelago server closed the connection and provided a status
+    /// code and a (possibly empty) reason.
+    ///
+    /// If the server closes the connection without any explanation, this is
+    /// reported as [Error::WebSocket] instead.
+    #[error("Archipelago server closed the connection ({code}){}", if reason.is_empty() { String::new() } else { format!(": {reason}") })]
+    ServerClosed {
+        /// The WebSocket close code. See [RFC 6455] for standard values.
+        ///
+        /// [RFC 6455]: https://datatracker.ietf.org/doc/html/rfc6455#section-7.4
+        code: u16,
+
+        /// The human-readable reason the server gave for closing the
+        /// connection.
+        reason: String,
+    },
+
     /// The Archipelago server rejected the connection.
     #[error("Archipelago refused connection: {}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", "))]
     ConnectionRefused(Vec<ConnectionError>),
@@ -65,6 +85,80 @@ impl Error {
     pub fn is_fatal(&self) -> bool {
         !matches!(self, Error::ProtocolError(_) | Error::InvalidPacket(_))
     }
+
+    /// Returns whether this error indicates that the connection was closed or
+    /// reset without the server explaining why. These errors are often
+    /// transient, so it's usually reasonable to try reconnecting right away.
+    pub fn is_connection_reset(&self) -> bool {
+        use tungstenite::error::ProtocolError::ResetWithoutClosingHandshake;
+        match self {
+            Error::WebSocket(
+                tungstenite::Error::ConnectionClosed
+                | tungstenite::Error::Protocol(ResetWithoutClosingHandshake),
+            ) => true,
+            Error::WebSocket(tungstenite::Error::Io(err)) | Error::Async(err) => matches!(
+                err.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns whether this error indicates that the server's host name
+    /// couldn't be resolved. This usually means that the URL is wrong or that
+    /// the local system has no network access.
+    pub fn is_dns_failure(&self) -> bool {
+        match self {
+            Error::WebSocket(tungstenite::Error::Url(UrlError::NoHostName)) => true,
+            Error::WebSocket(tungstenite::Error::Io(err)) => {
+                err.get_ref().is_some_and(|inner| inner.is::<DnsFailure>())
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether this error occurred while establishing or using a TLS
+    /// connection. This often indicates a configuration problem, such as an
+    /// untrusted certificate, that won't be fixed by retrying.
+    pub fn is_tls_error(&self) -> bool {
+        matches!(self, Error::WebSocket(tungstenite::Error::Tls(_)))
+    }
+}
+
+/// The inner error for an [io::Error] indicating that a host name couldn't be
+/// resolved.
+///
+/// This is only used to mark errors so that [Error::is_dns_failure] can
+/// distinguish them from other I/O errors.
+#[derive(ThisError, Debug)]
+#[error("failed to resolve {host}: {message}")]
+pub(crate) struct DnsFailure {
+    /// The host and port that couldn't be resolved.
+    host: String,
+
+    /// A description of why resolution failed.
+    message: String,
+}
+
+impl DnsFailure {
+    /// Returns a [tungstenite::Error] indicating that `host` couldn't be
+    /// resolved because of `message`. The error has the given `kind`.
+    pub(crate) fn into_error(
+        kind: io::ErrorKind,
+        host: impl Into<String>,
+        message: impl Into<String>,
+    ) -> tungstenite::Error {
+        tungstenite::Error::Io(io::Error::new(
+            kind,
+            DnsFailure {
+                host: host.into(),
+                message: message.into(),
+            },
+        ))
+    }
 }
 
 /// Possible individual errors that can cause an initial Archipelago connection
@@ -106,7 +200,9 @@ impl From<String> for ConnectionError {
         match value.as_str() {
             "InvalidSlot" => InvalidSlot,
             "InvalidGame" => InvalidGame,
-            "InvalidVersion" => InvalidVersion,
+            // The server sends IncompatibleVersion, but the protocol docs have
+            // historically called it InvalidVersion.
+            "IncompatibleVersion" | "InvalidVersion" => InvalidVersion,
             "InvalidPassword" => InvalidPassword,
             "InvalidItemsHandling" => InvalidItemsHandling,
             _ => Unknown(value),
@@ -134,6 +230,16 @@ pub enum ArgumentError {
         tags: UstrSet,
     },
 
+    /// The game parameter to [Client::connect] or [Connection::new] was an
+    /// empty string but [ConnectionOptions::tags] didn't contain a tag that
+    /// would allow connecting without a game.
+    ///
+    /// [Client::connect]: crate::Client::connect
+    /// [Connection::new]: crate::Connection::new
+    /// [ConnectionOptions::tags]: crate::ConnectionOptions::tags
+    #[error("game was empty")]
+    EmptyGame,
+
     /// The given location ID doesn't correspond to a location in the given
     /// game.
     #[error("{game} doesn't have a location with ID {location}")]
@@ -148,6 +254,18 @@ pub enum ArgumentError {
     /// The given slot number isn't an actual slot in this multiworld.
     #[error("this multiworld doesn't have a slot {0}")]
     InvalidSlot(u32),
+
+    /// The requested operation isn't available for spectator slots, such as
+    /// checking locations.
+    #[error("spectator slots can't do that")]
+    Spectator,
+
+    /// [Connection::new_with_fallbacks] was passed an empty list of URLs, so
+    /// there was nothing to connect to.
+    ///
+    /// [Connection::new_with_fallbacks]: crate::Connection::new_with_fallbacks
+    #[error("no URLs to connect to")]
+    NoUrls,
 }
 
 /// Errors caused by the Archipelago doing something that violates (our
@@ -186,10 +304,6 @@ pub enum ProtocolError {
         expected: &'static str,
     },
 
-    /// The `Connected` message included an empty players array.
-    #[error("Connected message includes no players")]
-    EmptyPlayers,
-
     /// The team and slot numbers for a player don't match anything in the
     /// players list.
     #[error("missing player on slot {slot}, team {team}")]
@@ -209,6 +323,20 @@ pub enum ProtocolError {
     #[error("no data package provided for {0}")]
     MissingGameData(Ustr),
 
+    /// A data package downloaded from the server has a different checksum
+    /// than the one the server advertised in `RoomInfo`.
+    #[error("data package for {game} has checksum {actual}, expected {expected}")]
+    ChecksumMismatch {
+        /// The name of the game whose data package was mismatched.
+        game: Ustr,
+
+        /// The checksum the server advertised in `RoomInfo`.
+        expected: String,
+
+        /// The checksum of the data package that was actually downloaded.
+        actual: String,
+    },
+
     /// An item has an ID that doesn't appear in its data package.
     #[error("item {id} is missing {game}'s data package")]
     MissingItem {
@@ -238,3 +366,107 @@ pub enum ProtocolError {
     #[error("server sent {0} response that we didn't request")]
     ResponseWithoutRequest(&'static str),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+
+    use tungstenite::error::ProtocolError::ResetWithoutClosingHandshake;
+
+    use super::*;
+
+    #[test]
+    fn is_connection_reset() {
+        assert!(Error::WebSocket(tungstenite::Error::ConnectionClosed).is_connection_reset());
+        assert!(
+            Error::WebSocket(tungstenite::Error::Protocol(ResetWithoutClosingHandshake))
+                .is_connection_reset()
+        );
+        assert!(
+            Error::WebSocket(tungstenite::Error::Io(
+                io::ErrorKind::ConnectionReset.into()
+            ))
+            .is_connection_reset()
+        );
+        assert!(Error::Async(io::ErrorKind::BrokenPipe.into()).is_connection_reset());
+
+        assert!(!Error::Async(io::ErrorKind::PermissionDenied.into()).is_connection_reset());
+        assert!(
+            !Error::WebSocket(tungstenite::Error::Url(UrlError::NoHostName)).is_connection_reset()
+        );
+        assert!(!Error::InvalidPacket("oops".into()).is_connection_reset());
+    }
+
+    #[test]
+    fn connection_error_from_string() {
+        use ConnectionError::*;
+        for (string, expected) in [
+            ("InvalidSlot", InvalidSlot),
+            ("InvalidGame", InvalidGame),
+            ("IncompatibleVersion", InvalidVersion),
+            ("InvalidVersion", InvalidVersion),
+            ("InvalidPassword", InvalidPassword),
+            ("InvalidItemsHandling", InvalidItemsHandling),
+        ] {
+            assert_eq!(
+                mem::discriminant(&ConnectionError::from(string.to_string())),
+                mem::discriminant(&expected),
+                "{string}"
+            );
+        }
+
+        assert!(matches!(
+            ConnectionError::from("SomethingNew".to_string()),
+            Unknown(error) if error == "SomethingNew"
+        ));
+    }
+
+    #[test]
+    fn is_dns_failure() {
+        assert!(Error::WebSocket(tungstenite::Error::Url(UrlError::NoHostName)).is_dns_failure());
+        assert!(
+            Error::WebSocket(DnsFailure::into_error(
+                io::ErrorKind::NotFound,
+                "example.invalid:38281",
+                "no addresses found"
+            ))
+            .is_dns_failure()
+        );
+
+        // Errors that merely look like resolution failures don't count.
+        assert!(
+            !Error::WebSocket(tungstenite::Error::Url(UrlError::UnableToConnect(
+                "example.invalid".into()
+            )))
+            .is_dns_failure()
+        );
+        assert!(
+            !Error::WebSocket(tungstenite::Error::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "failed to resolve example.invalid"
+            )))
+            .is_dns_failure()
+        );
+
+        assert!(
+            !Error::WebSocket(tungstenite::Error::Url(UrlError::UnsupportedUrlScheme))
+                .is_dns_failure()
+        );
+        assert!(!Error::WebSocket(tungstenite::Error::ConnectionClosed).is_dns_failure());
+        assert!(!Error::Async(io::ErrorKind::ConnectionReset.into()).is_dns_failure());
+    }
+
+    #[test]
+    fn is_tls_error() {
+        #[cfg(feature = "rustls")]
+        assert!(
+            Error::WebSocket(tungstenite::Error::Tls(
+                tungstenite::error::TlsError::InvalidDnsName
+            ))
+            .is_tls_error()
+        );
+
+        assert!(!Error::WebSocket(tungstenite::Error::ConnectionClosed).is_tls_error());
+        assert!(!Error::Async(io::ErrorKind::ConnectionReset.into()).is_tls_error());
+    }
+}
@@ -1,8 +1,9 @@
-use std::{collections::HashSet, sync::Arc, time::SystemTime};
+use std::time::{Duration, SystemTime};
+use std::{collections::HashSet, sync::Arc};
 
 use ustr::UstrSet;
 
-use crate::{Error, Location, Player, Print, protocol::Permission};
+use crate::{Error, Game, Location, Player, Print, protocol::Permission};
 
 /// Events from the Archipelago server that clients may want to handle.
 ///
@@ -20,6 +21,11 @@ pub enum Event {
     Updated(Vec<UpdatedField>),
 
     /// A message for the client to display to the player.
+    ///
+    /// Different kinds of messages are distinguished by their [Print] variant.
+    /// For example, the server's first-connection guidance is sent as
+    /// [Print::Tutorial], which clients may want to show in a help panel rather
+    /// than alongside other chat messages.
     Print(Print),
 
     /// Items have been received from the server (usually another world,
@@ -35,6 +41,35 @@ pub enum Event {
     /// ever received have been re-sent.
     ReceivedItems(usize),
 
+    /// The server re-sent all the items the player has ever received without
+    /// the client requesting it, usually because the server restarted.
+    ///
+    /// The client has discarded its previous [Client.received_items] and
+    /// replaced it with the re-sent list. This is always immediately followed
+    /// by [ReceivedItems](Event::ReceivedItems) with index 0. Clients that
+    /// track received items themselves should reset their tallies when they
+    /// see this, to avoid double-counting.
+    ItemsResynced,
+
+    /// The client has processed all the state the server sends upon
+    /// connecting, such as the initial [ReceivedItems](Event::ReceivedItems).
+    ///
+    /// This is emitted exactly once per connection. Events emitted between
+    /// [Connected](Event::Connected) and this represent things that happened
+    /// while the client was disconnected, and clients that reconnect may want
+    /// to handle them differently from live events (for example by not showing
+    /// a notification for every item received during a resync).
+    CatchUpComplete,
+
+    /// The server or multiworld may not be fully compatible with this client,
+    /// for example because the server is running a newer version of
+    /// Archipelago than this client knows about.
+    ///
+    /// This is emitted immediately after connecting, once for each entry in
+    /// [Client.compatibility_warnings]. The client continues to work as usual,
+    /// but some features may behave unexpectedly.
+    CompatibilityWarning(String),
+
     /// The client has encountered an error.
     ///
     /// Once this event has been emitted, the client should be considered
@@ -90,6 +125,15 @@ pub enum Event {
         /// any particular relationship to the current system's time.
         time: SystemTime,
 
+        /// How long ago the death occurred, according to `time` and the local
+        /// system clock at the time this event was created.
+        ///
+        /// This is `None` if `time` is in the future, which can happen if the
+        /// skew between the sender and the server differs from the skew
+        /// between the server and the local system. Games can use this to
+        /// ignore stale death links.
+        time_ago: Option<Duration>,
+
         /// The time that the death link was sent, according to the sender with
         /// no skew adjustment at all.
         server_time: SystemTime,
@@ -103,6 +147,35 @@ pub enum Event {
         source: String,
     },
 
+    /// The player's [Client.hint_points] changed, either because they checked
+    /// a location, spent points on a hint, or the server otherwise updated
+    /// them.
+    ///
+    /// When this is caused by a server update, it's emitted after the
+    /// [Updated](Event::Updated) event that contains the corresponding
+    /// [UpdatedField::HintPoints]. It's only emitted if the value actually
+    /// changed, so the server confirming a change the client already made
+    /// locally won't emit it twice.
+    HintPointsChanged {
+        /// The previous number of hint points.
+        old: i64,
+
+        /// The new number of hint points.
+        new: i64,
+    },
+
+    /// A player's alias has changed.
+    ///
+    /// This is emitted after the [Updated](Event::Updated) event that contains
+    /// the corresponding [UpdatedField::Players].
+    PlayerRenamed {
+        /// The player with their new alias.
+        player: Arc<Player>,
+
+        /// The player's alias before the change.
+        old_alias: String,
+    },
+
     /// The value associated with a key in the server's data storage was
     /// updated. This is only emitted after [Client.watch] is called, or if
     /// [Client.set] or [Client.change] is called with `emit_event` set to
@@ -160,9 +233,22 @@ pub enum UpdatedField {
     /// [Client.assert_player] to access the new ones.
     Players(Vec<Arc<Player>>),
 
+    /// One or more players that weren't previously known to the client have
+    /// joined the room.
+    ///
+    /// This includes the new [Player] structs.
+    PlayersAdded(Vec<Arc<Player>>),
+
     /// Additional locations have been checked, usually from a co-op player in
     /// the same slot.
     ///
     /// This includes all newly-checked locations.
     CheckedLocations(Vec<Location>),
+
+    /// One or more games' data packages have been refreshed by
+    /// [Client.refresh_data_package].
+    ///
+    /// This contains the *previous* [Game] structs for games that were
+    /// replaced. Use [Client.assert_game] to access the new ones.
+    GameData(Vec<Game>),
 }
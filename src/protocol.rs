@@ -1,7 +1,7 @@
-use std::{collections::HashMap, fmt::Display, mem, time::SystemTime};
+use std::{collections::HashMap, fmt::Display, time::SystemTime};
 
 use bitflags::bitflags;
-use serde::{Deserialize, Deserializer, Serialize, de::DeserializeOwned};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use serde_with::{DisplayFromStr, TimestampSeconds, serde_as};
@@ -35,11 +35,10 @@ pub(crate) enum ClientMessage {
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "cmd")]
-pub(crate) enum ServerMessage<S: DeserializeOwned + 'static> {
+pub(crate) enum ServerMessage {
     RoomInfo(RoomInfo),
     ConnectionRefused(ConnectionRefused),
-    #[serde(deserialize_with = "deserialize_connected_with_optional_slot_data")]
-    Connected(Connected<S>),
+    Connected(Connected),
     ReceivedItems(ReceivedItems),
     LocationInfo(LocationInfo),
     RoomUpdate(RoomUpdate),
@@ -54,7 +53,7 @@ pub(crate) enum ServerMessage<S: DeserializeOwned + 'static> {
     SetReply(SetReply),
 }
 
-impl<S: DeserializeOwned + 'static> ServerMessage<S> {
+impl ServerMessage {
     /// Returns the name of this message's type.
     pub(crate) fn type_name(&self) -> &'static str {
         use ServerMessage::*;
@@ -122,20 +121,66 @@ pub(crate) struct NetworkPlayer {
     pub(crate) name: Ustr,
 }
 
+/// The raw network representation of an item in a particular location, as
+/// defined by the [Archipelago protocol].
+///
+/// [Archipelago protocol]: https://github.com/ArchipelagoMW/Archipelago/blob/main/docs/network%20protocol.md#networkitem
+///
+/// This is only exposed so that it can be passed through custom payloads, such
+/// as [Event::Bounce](crate::Event::Bounce) data. Use
+/// [Client::hydrate_item](crate::Client::hydrate_item) to convert it into a
+/// [LocatedItem](crate::LocatedItem).
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct NetworkItem {
+pub struct NetworkItem {
     pub(crate) item: i64,
     pub(crate) location: i64,
     pub(crate) player: u32,
     pub(crate) flags: NetworkItemFlags,
 }
 
+impl NetworkItem {
+    /// Creates a new network item.
+    pub fn new(item: i64, location: i64, player: u32, flags: NetworkItemFlags) -> Self {
+        NetworkItem {
+            item,
+            location,
+            player,
+            flags,
+        }
+    }
+
+    /// The item's ID.
+    pub fn item(&self) -> i64 {
+        self.item
+    }
+
+    /// The ID of the location that contains the item.
+    pub fn location(&self) -> i64 {
+        self.location
+    }
+
+    /// A player slot on the local team.
+    ///
+    /// Which player this refers to depends on where the item came from. For
+    /// items the server sends in `ReceivedItems`, it's the player who found
+    /// the item. For scouted locations, it's the player who will receive it.
+    pub fn player(&self) -> u32 {
+        self.player
+    }
+
+    /// Flags indicating how important the item is.
+    pub fn flags(&self) -> NetworkItemFlags {
+        self.flags
+    }
+}
+
 bitflags! {
+    /// Flags that indicate how important an item is to its receiver's game.
     #[repr(transparent)]
-    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
     #[serde(from = "u8")]
     #[serde(into = "u8")]
-    pub(crate) struct NetworkItemFlags: u8 {
+    pub struct NetworkItemFlags: u8 {
         /// The item can unlock logical advancement.
         const PROGRESSION = 0b001;
 
@@ -173,6 +218,7 @@ pub(crate) struct NetworkSlot {
     pub(crate) name: Ustr,
     pub(crate) game: Ustr,
     pub(crate) r#type: SlotType,
+    #[serde(default)]
     pub(crate) group_members: Vec<u32>,
 }
 
@@ -262,7 +308,8 @@ pub(crate) struct UpdateHint {
     pub(crate) status: HintStatus,
 }
 
-#[derive(Debug, Clone, Default, Serialize_repr)]
+/// How much a player wants a hinted item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum HintStatus {
     #[default]
@@ -270,6 +317,26 @@ pub enum HintStatus {
     NoPriority = 10,
     Avoid = 20,
     Priority = 30,
+    /// The hinted item has already been found. The server sets this
+    /// automatically, so it can't be passed to
+    /// [create_hints](crate::Client::create_hints) or
+    /// [update_hint](crate::Client::update_hint).
+    Found = 40,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct NetworkHint {
+    pub(crate) receiving_player: u32,
+    pub(crate) finding_player: u32,
+    pub(crate) location: i64,
+    pub(crate) item: i64,
+    pub(crate) found: bool,
+    #[serde(default)]
+    pub(crate) entrance: String,
+    #[serde(default)]
+    pub(crate) item_flags: NetworkItemFlags,
+    #[serde(default)]
+    pub(crate) status: HintStatus,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -438,52 +505,29 @@ pub(crate) struct ConnectionRefused {
     pub(crate) errors: Vec<String>,
 }
 
+// None of the response structs use `deny_unknown_fields`, so new fields added
+// by the server are ignored. Fields that aren't essential to establishing a
+// session are marked `#[serde(default)]` so that older or newer servers that
+// omit them can still be connected to.
 #[serde_as]
 #[derive(Debug, Clone, Deserialize)]
-pub(crate) struct Connected<S> {
+pub(crate) struct Connected {
     pub(crate) team: u32,
     pub(crate) slot: u32,
     pub(crate) players: Vec<NetworkPlayer>,
     pub(crate) missing_locations: Vec<i64>,
     pub(crate) checked_locations: Vec<i64>,
-    pub(crate) slot_data: S,
+    // This is decoded into the client's slot data type later on, so that the
+    // caller can control how that happens. It's omitted entirely if the client
+    // didn't request slot data.
+    #[serde(default)]
+    pub(crate) slot_data: Value,
     #[serde_as(as = "HashMap<DisplayFromStr, _>")]
     pub(crate) slot_info: HashMap<u32, NetworkSlot>,
+    #[serde(default)]
     pub(crate) hint_points: i64,
 }
 
-/// Deserializes a value of `Connected<S>`, except that if `S` is
-/// [json_serde::Value], this will allow `slot_data` to be omitted entirely.
-fn deserialize_connected_with_optional_slot_data<'de, S, D>(
-    deserializer: D,
-) -> Result<Connected<S>, D::Error>
-where
-    S: DeserializeOwned + 'static,
-    D: Deserializer<'de>,
-{
-    Ok(if try_specialize::static_type_eq::<S, ()>() {
-        let inner = Connected::<Option<()>>::deserialize(deserializer)?;
-        let connected = Connected {
-            team: inner.team,
-            slot: inner.slot,
-            players: inner.players,
-            missing_locations: inner.missing_locations,
-            checked_locations: inner.checked_locations,
-            slot_data: (),
-            slot_info: inner.slot_info,
-            hint_points: inner.hint_points,
-        };
-
-        // Safety: We've verified that S is Value above.
-        let typed_connected =
-            unsafe { mem::transmute_copy::<Connected<()>, Connected<S>>(&connected) };
-        mem::forget(connected);
-        typed_connected
-    } else {
-        Connected::<S>::deserialize(deserializer)?
-    })
-}
-
 #[derive(Debug, Clone, Deserialize)]
 pub(crate) struct ReceivedItems {
     pub(crate) index: usize,
@@ -524,6 +568,12 @@ pub(crate) struct DataPackageObject {
 pub(crate) struct GameData {
     pub(crate) item_name_to_id: HashMap<Ustr, i64>,
     pub(crate) location_name_to_id: HashMap<Ustr, i64>,
+    // Older servers include name groups in the data package. Newer ones don't,
+    // so these are often empty.
+    #[serde(default, skip_serializing_if = "UstrMap::is_empty")]
+    pub(crate) item_name_groups: UstrMap<Vec<Ustr>>,
+    #[serde(default, skip_serializing_if = "UstrMap::is_empty")]
+    pub(crate) location_name_groups: UstrMap<Vec<Ustr>>,
     pub(crate) checksum: String,
 }
 
@@ -532,6 +582,10 @@ pub(crate) struct GameData {
 #[derive(Debug, Clone, Deserialize)]
 pub(crate) struct InvalidPacket {
     pub(crate) text: String,
+    /// The `cmd` of the client message that caused this error, if the server
+    /// provided it.
+    #[serde(default)]
+    pub(crate) original_cmd: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -547,3 +601,80 @@ pub(crate) struct SetReply {
     // See https://github.com/ArchipelagoMW/Archipelago/issues/5829
     pub(crate) slot: Option<u32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Parses `json` as a single [ServerMessage] and returns it as
+    /// [Connected].
+    fn parse_connected(json: Value) -> Connected {
+        match serde_json::from_value(json).unwrap() {
+            ServerMessage::Connected(connected) => connected,
+            message => panic!("expected Connected, got {}", message.type_name()),
+        }
+    }
+
+    #[test]
+    fn connected_with_extra_fields() {
+        let connected = parse_connected(json!({
+            "cmd": "Connected",
+            "team": 0,
+            "slot": 1,
+            "players": [
+                {"team": 0, "slot": 1, "alias": "Alias", "name": "Name", "new": true}
+            ],
+            "missing_locations": [10],
+            "checked_locations": [11],
+            "slot_data": {"key": "value"},
+            "slot_info": {
+                "1": {
+                    "name": "Name",
+                    "game": "Game",
+                    "type": 1,
+                    "group_members": [],
+                    "new": [1, 2, 3]
+                }
+            },
+            "hint_points": 5,
+            "new": {"nested": "field"}
+        }));
+        assert_eq!(connected.players[0].alias, "Alias");
+        assert_eq!(connected.missing_locations, [10]);
+        assert_eq!(connected.checked_locations, [11]);
+        assert_eq!(connected.slot_info[&1].game, "Game");
+        assert_eq!(connected.hint_points, 5);
+    }
+
+    #[test]
+    fn connected_without_optional_fields() {
+        let connected = parse_connected(json!({
+            "cmd": "Connected",
+            "team": 0,
+            "slot": 1,
+            "players": [],
+            "missing_locations": [],
+            "checked_locations": [],
+            "slot_info": {"1": {"name": "Name", "game": "Game", "type": 1}}
+        }));
+        assert!(connected.slot_data.is_null());
+        assert!(connected.slot_info[&1].group_members.is_empty());
+        assert_eq!(connected.hint_points, 0);
+    }
+
+    #[test]
+    fn connected_requires_location_lists() {
+        assert!(
+            serde_json::from_value::<ServerMessage>(json!({
+                "cmd": "Connected",
+                "team": 0,
+                "slot": 1,
+                "players": [],
+                "slot_info": {}
+            }))
+            .is_err()
+        );
+    }
+}
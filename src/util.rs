@@ -1,6 +1,7 @@
-use smol::{fs, io};
+use smol::{fs, io, io::AsyncWriteExt};
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
-use std::{fmt::Debug, iter::FusedIterator};
+use std::{fmt::Debug, iter::FusedIterator, mem};
 
 mod signed_duration;
 
@@ -28,8 +29,16 @@ pub trait Iter<T>: UnsizedIter<T> + ExactSizeIterator {}
 
 impl<I, T> Iter<T> for I where I: UnsizedIter<T> + ExactSizeIterator {}
 
+/// The string that [write_file_atomic] adds to the names of its temporary files.
+const TEMP_FILE_MARKER: &str = "-tmp-";
+
 /// Writes a file atomically by writing to an adjacent file with additional
 /// random characters in its name and then moving that to the desired location.
+///
+/// The temporary file is flushed to disk before it's renamed so that a crash
+/// can't leave a truncated file at `path`. Because each writer uses its own
+/// temporary file, concurrent writes to the same path are safe: the last rename
+/// wins, and readers only ever see a complete file.
 pub(crate) async fn write_file_atomic(
     path: impl AsRef<Path>,
     contents: impl AsRef<[u8]>,
@@ -47,18 +56,106 @@ pub(crate) async fn write_file_atomic(
             )
         })
         .to_owned();
-    tmp_basename.push(format!("-tmp-{:0}", rand::random::<u32>()));
+    tmp_basename.push(format!("{TEMP_FILE_MARKER}{:0}", rand::random::<u32>()));
     if let Some(ext) = path.extension() {
+        tmp_basename.push(".");
         tmp_basename.push(ext);
     }
     tmp_path.push(tmp_basename);
-    fs::write(&tmp_path, contents).await?;
-    fs::rename(tmp_path, path).await?;
 
-    Ok(())
+    let result = async {
+        let mut file = fs::File::create(&tmp_path).await?;
+        file.write_all(contents.as_ref()).await?;
+        file.sync_all().await?;
+        mem::drop(file);
+        fs::rename(&tmp_path, path).await
+    }
+    .await;
+    if result.is_err() {
+        // Don't leave the temporary file lying around if we couldn't move it
+        // into place.
+        mem::drop(fs::remove_file(&tmp_path).await);
+    }
+    result
+}
+
+/// Returns whether `name` is the name of a temporary file created by
+/// [write_file_atomic], which may still be in the process of being written.
+pub(crate) fn is_temp_file(name: &OsStr) -> bool {
+    name.to_str()
+        .is_some_and(|name| name.contains(TEMP_FILE_MARKER))
 }
 
 /// Returns a version of `name` that's safe to use as a pathname.
 pub(crate) fn sanitize_file_name(name: impl AsRef<str>) -> String {
     sanitise_file_name::sanitise_with_options(name.as_ref(), &SANITIZE_FILE_NAME_OPTIONS)
 }
+
+/// A uniquely-named temporary directory that's deleted when this is dropped.
+#[cfg(test)]
+pub(crate) struct TestDir(PathBuf);
+
+#[cfg(test)]
+impl TestDir {
+    /// Creates a new empty temporary directory.
+    pub(crate) fn new() -> Self {
+        let path =
+            std::env::temp_dir().join(format!("archipelago_rs-test-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&path).unwrap();
+        TestDir(path)
+    }
+
+    /// The path to the directory.
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        mem::drop(std::fs::remove_dir_all(&self.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn write_file_atomic_writes_contents() {
+        let dir = TestDir::new();
+        let path = dir.path().join("file.json");
+        smol::block_on(write_file_atomic(&path, "first")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+        smol::block_on(write_file_atomic(&path, "second")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+
+        // The temporary file should have been renamed into place.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn write_file_atomic_cleans_up_on_failure() {
+        let dir = TestDir::new();
+        // Renaming a file over a non-empty directory always fails.
+        let path = dir.path().join("file.json");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("child"), "").unwrap();
+
+        assert!(smol::block_on(write_file_atomic(&path, "contents")).is_err());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn write_file_atomic_temp_file_names() {
+        let dir = TestDir::new();
+        let path = dir.path().join("file.json");
+        smol::block_on(write_file_atomic(&path, "contents")).unwrap();
+        assert!(!is_temp_file(path.file_name().unwrap()));
+        assert!(is_temp_file(OsStr::new("file-tmp-1234.json")));
+    }
+}
@@ -0,0 +1,1398 @@
+use archipelago_rs::{
+    ArgumentError, Cache, Client, ConnectionOptions, CreateAsHint, Error, Event, HintStatus,
+    ItemHandling, LocatedItem, NetworkItem, NetworkItemFlags, ProtocolError, Transport,
+    UpdatedField,
+};
+use std::collections::HashMap;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use serde_json::json;
+
+mod common;
+
+use common::{Room, Server, catch_up, connect, connect_with, resolve, update_until};
+
+#[test]
+fn location_counts() {
+    let mut room = Room::default();
+    room.connected["missing_locations"] = json!([11, 12]);
+    room.connected["checked_locations"] = json!([10]);
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    assert_eq!(client.checked_locations().len(), 1);
+    assert_eq!(client.unchecked_locations().len(), 2);
+
+    client.mark_checked([11]).unwrap();
+    assert_eq!(client.checked_locations().len(), 2);
+    assert_eq!(client.unchecked_locations().len(), 1);
+}
+
+/// Returns a room in which the connecting slot, slot 3, has type `slot_type`
+/// but doesn't appear in the players list.
+fn room_without_connecting_player(slot_type: u8) -> Room {
+    let mut room = Room::default();
+    room.room_info["games"] = json!(["Archipelago", "Test Game", "Other Game"]);
+    room.connected["slot"] = json!(3);
+    room.connected["missing_locations"] = json!([]);
+    room.connected["slot_info"]["3"] = json!({
+        "name": "Watcher",
+        "game": "Archipelago",
+        "type": slot_type,
+        "group_members": []
+    });
+    room
+}
+
+#[test]
+fn text_only_slot_missing_from_players() {
+    let (client, _peer) = connect_with::<serde_json::Value>(
+        room_without_connecting_player(1),
+        "Watcher",
+        None,
+        ConnectionOptions::new().no_cache().tags(["TextOnly"]),
+    );
+    assert_eq!(client.this_player().name(), "Watcher");
+    assert_eq!(client.players().count(), 4);
+}
+
+#[test]
+fn spectator_slot_missing_from_players() {
+    let (client, _peer) = connect_with::<serde_json::Value>(
+        room_without_connecting_player(0),
+        "Watcher",
+        Some("Archipelago"),
+        ConnectionOptions::new().no_cache(),
+    );
+    assert!(client.is_spectator());
+    assert_eq!(client.this_player().name(), "Watcher");
+}
+
+#[test]
+fn player_slot_missing_from_players() {
+    let server = Server::start(room_without_connecting_player(1));
+    let result = smol::block_on(Client::<serde_json::Value>::connect(
+        server.url.clone(),
+        "Watcher",
+        Some("Archipelago"),
+        ConnectionOptions::new().no_cache(),
+    ));
+    assert!(matches!(
+        result,
+        Err(Error::ProtocolError(ProtocolError::MissingPlayer {
+            team: 0,
+            slot: 3
+        }))
+    ));
+}
+
+#[test]
+fn empty_game() {
+    let server = Server::start(Room::default());
+    let result = smol::block_on(Client::<serde_json::Value>::connect(
+        server.url.clone(),
+        "Player1",
+        Some(""),
+        ConnectionOptions::new().no_cache(),
+    ));
+    assert!(matches!(
+        result,
+        Err(Error::ArgumentError(ArgumentError::EmptyGame))
+    ));
+}
+
+#[test]
+fn text_only_empty_game() {
+    let (client, _peer) = connect_with::<serde_json::Value>(
+        room_without_connecting_player(1),
+        "Watcher",
+        Some(""),
+        ConnectionOptions::new().no_cache().tags(["TextOnly"]),
+    );
+    assert_eq!(client.this_player().name(), "Watcher");
+}
+
+#[test]
+fn refresh_data_package_writes_to_cache() {
+    let room = Room::default();
+    let cache = Cache::memory();
+    let (mut client, mut peer) = connect_with::<serde_json::Value>(
+        room.clone(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().cache(cache.clone()),
+    );
+    catch_up(&mut client);
+    smol::block_on(cache.clear()).unwrap();
+
+    client
+        .refresh_data_package(Some(vec!["Test Game".into()]))
+        .unwrap();
+    let request = peer.expect("GetDataPackage");
+    peer.send_data_packages(&room, request);
+    update_until(&mut client, |event| {
+        matches!(event, Event::Updated(fields)
+            if fields.iter().any(|field| matches!(field, UpdatedField::GameData(_))))
+    });
+
+    assert_eq!(smol::block_on(cache.stats()).unwrap().games(), 1);
+}
+
+#[test]
+fn scout_locations_with_hint_status() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    let receiver = client.scout_locations_with_hint_status([10], HintStatus::NoPriority);
+    let create_hints = peer.expect("CreateHints");
+    assert_eq!(create_hints["locations"], json!([10]));
+    assert_eq!(create_hints["status"], json!(10));
+    let scouts = peer.expect("LocationScouts");
+    assert_eq!(scouts["locations"], json!([10]));
+    assert_eq!(scouts["create_as_hint"], json!(0));
+
+    peer.send(json!([{
+        "cmd": "LocationInfo",
+        "locations": [{"item": 101, "location": 10, "player": 2, "flags": 0}]
+    }]));
+    let items = resolve(&mut client, receiver).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].item().name(), "Bow");
+}
+
+#[test]
+fn scout_locations_with_hint_status_propagates_hint_errors() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    peer.close();
+    update_until(&mut client, |event| matches!(event, Event::Error(_)));
+
+    let receiver = client.scout_locations_with_hint_status([10], HintStatus::NoPriority);
+    assert!(matches!(receiver.try_recv(), Ok(Err(_))));
+}
+
+#[test]
+fn dropped_scout_doesnt_steal_responses() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    let dropped = client.scout_locations([10], CreateAsHint::No);
+    let kept = client.scout_locations([11], CreateAsHint::No);
+    mem::drop(dropped);
+    peer.expect("LocationScouts");
+    peer.expect("LocationScouts");
+
+    peer.send(json!([
+        {
+            "cmd": "LocationInfo",
+            "locations": [{"item": 1, "location": 10, "player": 1, "flags": 0}]
+        },
+        {
+            "cmd": "LocationInfo",
+            "locations": [{"item": 2, "location": 11, "player": 1, "flags": 0}]
+        }
+    ]));
+    let items = resolve(&mut client, kept).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].location().name(), "Chest 2");
+    assert_eq!(items[0].item().name(), "Shield");
+    assert_eq!(client.pending_request_count(), 0);
+}
+
+#[test]
+fn scouted_item_cache() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+    assert!(client.scouted_item(1, 10).is_none());
+
+    let receiver = client.scout_locations([10], CreateAsHint::No);
+    peer.expect("LocationScouts");
+    peer.send(json!([{
+        "cmd": "LocationInfo",
+        "locations": [{"item": 2, "location": 10, "player": 1, "flags": 0}]
+    }]));
+    resolve(&mut client, receiver).unwrap();
+
+    let item = client.scouted_item(1, 10).unwrap();
+    assert_eq!(item.item().name(), "Shield");
+    assert_eq!(item.location().name(), "Chest 1");
+    assert!(client.scouted_item(2, 10).is_none());
+    assert!(client.scouted_item(1, 11).is_none());
+
+    client.clear_scout_cache();
+    assert!(client.scouted_item(1, 10).is_none());
+}
+
+#[test]
+fn race_mode() {
+    let (mut client, _peer) = connect(Room {
+        race_mode: 1,
+        ..Default::default()
+    });
+    assert_eq!(client.race_mode(), None);
+    assert!(!client.is_race_mode());
+
+    catch_up(&mut client);
+    assert_eq!(client.race_mode(), Some(true));
+    assert!(client.is_race_mode());
+}
+
+#[test]
+fn not_race_mode() {
+    let (mut client, _peer) = connect(Room::default());
+    catch_up(&mut client);
+    assert_eq!(client.race_mode(), Some(false));
+    assert!(!client.is_race_mode());
+}
+
+#[test]
+fn say_to() {
+    let mut room = Room::default();
+    room.connected["players"][1]["alias"] = json!("Dark Souls Fan");
+    let (mut client, mut peer) = connect(room);
+    catch_up(&mut client);
+
+    client.say_to("Player2", "hello".into()).unwrap();
+    assert_eq!(peer.expect("Say")["text"], "Dark Souls Fan: hello");
+
+    client.say_to("Some One", "hello".into()).unwrap();
+    assert_eq!(peer.expect("Say")["text"], "Some One: hello");
+}
+
+#[test]
+fn update_map() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    client
+        .update_map(
+            "progress",
+            HashMap::from([("chests".to_string(), json!(3))]),
+            true,
+        )
+        .unwrap();
+    let set = peer.expect("Set");
+    assert_eq!(set["key"], "progress");
+    assert_eq!(set["default"], json!({}));
+    assert_eq!(
+        set["operations"],
+        json!([{"operation": "update", "value": {"chests": 3}}])
+    );
+    assert_eq!(set["want_reply"], true);
+}
+
+#[test]
+fn set_many() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    client
+        .set_many([("first", json!(1)), ("second", json!("two"))], false)
+        .unwrap();
+    let messages = peer.recv();
+    let [first, second] = messages.as_slice() else {
+        panic!("expected both Sets in one frame, got {messages:?}");
+    };
+    for (message, key, value) in [(first, "first", json!(1)), (second, "second", json!("two"))] {
+        assert_eq!(message["cmd"], "Set");
+        assert_eq!(message["key"], key);
+        assert_eq!(
+            message["operations"],
+            json!([{"operation": "replace", "value": value}])
+        );
+        assert_eq!(message["want_reply"], false);
+    }
+
+    // An empty batch doesn't send anything.
+    client
+        .set_many(Vec::<(String, serde_json::Value)>::new(), false)
+        .unwrap();
+    peer.assert_no_message();
+}
+
+#[test]
+fn spectator_item_handling() {
+    let mut room = Room::default();
+    room.room_info["games"] = json!(["Archipelago", "Test Game", "Other Game"]);
+    room.connected["slot"] = json!(3);
+    room.connected["missing_locations"] = json!([]);
+    room.connected["players"]
+        .as_array_mut()
+        .unwrap()
+        .push(json!({"team": 0, "slot": 3, "alias": "Watcher", "name": "Watcher"}));
+    room.connected["slot_info"]["3"] = json!({
+        "name": "Watcher",
+        "game": "Archipelago",
+        "type": 0,
+        "group_members": []
+    });
+    let (mut client, mut peer) = connect_with::<serde_json::Value>(
+        room,
+        "Watcher",
+        Some("Archipelago"),
+        ConnectionOptions::new()
+            .no_cache()
+            .receive_items(ItemHandling::None),
+    );
+    catch_up(&mut client);
+    assert!(client.is_spectator());
+
+    assert!(matches!(
+        client.mark_checked_unchecked([10]),
+        Err(Error::ArgumentError(ArgumentError::Spectator))
+    ));
+    assert!(matches!(
+        client.sync(),
+        Err(Error::ArgumentError(ArgumentError::Spectator))
+    ));
+    assert!(matches!(
+        client.update_connection(Some(ItemHandling::default()), None::<[&str; 0]>),
+        Err(Error::ArgumentError(ArgumentError::Spectator))
+    ));
+    peer.assert_no_message();
+
+    // Changing tags or opting out of items is still allowed.
+    client
+        .update_connection(Some(ItemHandling::None), Some(["Tracker"]))
+        .unwrap();
+    peer.expect("ConnectUpdate");
+}
+
+#[test]
+fn items_handling() {
+    let (mut client, mut peer) = connect_with::<serde_json::Value>(
+        Room::default(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new()
+            .no_cache()
+            .receive_items(ItemHandling::None),
+    );
+    catch_up(&mut client);
+    assert_eq!(client.items_handling(), &ItemHandling::None);
+
+    let handling = ItemHandling::OtherWorlds {
+        own_world: true,
+        starting_inventory: false,
+    };
+    client
+        .update_connection(Some(handling.clone()), None::<[&str; 0]>)
+        .unwrap();
+    assert_eq!(client.items_handling(), &handling);
+    assert_eq!(peer.expect("ConnectUpdate")["items_handling"], 0b011);
+
+    // Updating only the tags leaves the item handling alone.
+    client.update_connection(None, Some(["Tracker"])).unwrap();
+    assert_eq!(client.items_handling(), &handling);
+    assert!(peer.expect("ConnectUpdate")["items_handling"].is_null());
+}
+
+#[test]
+fn hydrate_item() {
+    let (mut client, _peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    // Player2 found Player1's sword in their cave.
+    let network = NetworkItem::new(1, 110, 2, NetworkItemFlags::PROGRESSION);
+    assert_eq!(network.item(), 1);
+    assert_eq!(network.location(), 110);
+    assert_eq!(network.player(), 2);
+    assert_eq!(network.flags(), NetworkItemFlags::PROGRESSION);
+
+    let item = client.hydrate_item(network, 1).unwrap();
+    assert_eq!(item.item().name(), "Sword");
+    assert_eq!(item.location().name(), "Cave");
+    assert_eq!(item.sender().name(), "Player2");
+    assert_eq!(item.receiver().name(), "Player1");
+    assert!(item.is_progression());
+
+    assert!(
+        client
+            .hydrate_item(NetworkItem::new(1, 110, 7, NetworkItemFlags::empty()), 1)
+            .is_err()
+    );
+}
+
+#[test]
+fn item_flags_round_trip() {
+    let mut room = Room::default();
+    room.after_connected.push(json!({
+        "cmd": "ReceivedItems",
+        "index": 0,
+        "items": [
+            {"item": 1, "location": 110, "player": 2, "flags": 0b101},
+            {"item": 2, "location": 111, "player": 2, "flags": 0b1010}
+        ]
+    }));
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    let [trap, unknown] = client.received_items() else {
+        panic!("expected two received items");
+    };
+    assert_eq!(
+        trap.flags(),
+        NetworkItemFlags::PROGRESSION | NetworkItemFlags::TRAP
+    );
+    assert!(trap.is_progression() && trap.is_trap() && !trap.is_useful());
+    // Bits this crate doesn't know about are passed along verbatim.
+    assert_eq!(unknown.flags().bits(), 0b1010);
+    assert!(unknown.is_useful());
+
+    let flags = NetworkItemFlags::USEFUL | NetworkItemFlags::from_bits_retain(0b1000);
+    let item = client
+        .hydrate_item(NetworkItem::new(1, 110, 2, flags), 1)
+        .unwrap();
+    assert_eq!(item.flags(), flags);
+}
+
+#[test]
+fn hint_costs() {
+    let mut room = Room::default();
+    room.room_info["hint_cost"] = json!(50);
+    room.connected["hint_points"] = json!(5);
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    // 50% of 3 locations, rounded down.
+    assert_eq!(client.points_per_hint(), 1);
+    assert_eq!(client.affordable_hints(), 5);
+}
+
+#[test]
+fn hint_costs_round_up_to_one() {
+    let mut room = Room::default();
+    room.room_info["hint_cost"] = json!(10);
+    room.connected["hint_points"] = json!(2);
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    // 10% of 3 locations rounds down to 0, but the server charges at least 1.
+    assert_eq!(client.points_per_hint(), 1);
+    assert_eq!(client.affordable_hints(), 2);
+}
+
+#[test]
+fn free_hints() {
+    let mut room = Room::default();
+    room.room_info["hint_cost"] = json!(0);
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    assert_eq!(client.points_per_hint(), 0);
+    assert_eq!(client.affordable_hints(), u64::MAX);
+}
+
+#[test]
+fn negative_hint_points() {
+    let mut room = Room::default();
+    room.room_info["hint_cost"] = json!(100);
+    room.connected["hint_points"] = json!(-4);
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    assert_eq!(client.points_per_hint(), 3);
+    assert_eq!(client.affordable_hints(), 0);
+}
+
+#[test]
+fn hint_points_changed_on_check() {
+    let mut room = Room::default();
+    room.room_info["location_check_points"] = json!(2);
+    room.connected["hint_points"] = json!(1);
+    let (mut client, mut peer) = connect(room);
+    catch_up(&mut client);
+
+    client.mark_checked([10]).unwrap();
+    peer.expect("LocationChecks");
+    let events = client.update();
+    let [Event::HintPointsChanged { old: 1, new: 3 }] = events.as_slice() else {
+        panic!("expected HintPointsChanged from 1 to 3");
+    };
+    assert_eq!(client.hint_points(), 3);
+
+    // The server confirming the change doesn't emit it again, and neither
+    // does checking the same location twice.
+    peer.send(json!([{
+        "cmd": "RoomUpdate",
+        "hint_points": 3,
+        "checked_locations": [10]
+    }]));
+    let mut events = update_until(&mut client, |event| matches!(event, Event::Updated(_)));
+    client.mark_checked([10]).unwrap();
+    events.extend(client.update());
+    assert!(
+        !events
+            .iter()
+            .any(|event| matches!(event, Event::HintPointsChanged { .. }))
+    );
+}
+
+#[test]
+fn my_hinted_unchecked_receipts() {
+    let mut room = Room::default();
+    room.connected["missing_locations"] = json!([10, 11]);
+    room.connected["checked_locations"] = json!([12]);
+    let (mut client, mut peer) = connect(room);
+    catch_up(&mut client);
+
+    let receiver = client.my_hinted_unchecked_receipts();
+    let get = peer.expect("Get");
+    assert_eq!(get["keys"], json!(["_read_hints_0_1"]));
+    let hint = |receiving: u32, finding: u32, location: i64, item: i64, flags: u8, found: bool| {
+        json!({
+            "receiving_player": receiving,
+            "finding_player": finding,
+            "location": location,
+            "item": item,
+            "found": found,
+            "item_flags": flags
+        })
+    };
+    peer.send(json!([{
+        "cmd": "Retrieved",
+        "keys": {"_read_hints_0_1": [
+            // Progression in an unchecked local location.
+            hint(1, 1, 10, 1, 1, false),
+            // Progression in a location that's already been checked locally.
+            hint(1, 1, 12, 2, 1, false),
+            // Progression in another world that hasn't been found.
+            hint(1, 2, 110, 2, 1, false),
+            // Filler in another world.
+            hint(1, 2, 111, 3, 0, false),
+            // Progression in another world that's already been found.
+            hint(1, 2, 111, 3, 1, true),
+            // Progression for another player.
+            hint(2, 1, 11, 101, 1, false),
+        ]}
+    }]));
+
+    let hints = resolve(&mut client, receiver).unwrap();
+    let found = hints
+        .iter()
+        .map(|hint| {
+            (
+                hint.item().location().name().as_str(),
+                hint.item().item().name().as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(found, [("Chest 1", "Sword"), ("Cave", "Shield")]);
+}
+
+#[test]
+fn all_local_locations_checked() {
+    let mut room = Room::default();
+    room.connected["missing_locations"] = json!([11, 12]);
+    room.connected["checked_locations"] = json!([10]);
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    assert!(!client.all_local_locations_checked());
+    client.mark_checked([11]).unwrap();
+    assert!(!client.all_local_locations_checked());
+    client.mark_checked([12]).unwrap();
+    assert!(client.all_local_locations_checked());
+}
+
+#[test]
+fn all_local_locations_checked_without_locations() {
+    let mut room = Room::default();
+    room.connected["missing_locations"] = json!([]);
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    assert!(client.all_local_locations_checked());
+}
+
+#[test]
+fn player_by_name_and_alias() {
+    let mut room = Room::default();
+    // Another team has a player with the same name.
+    room.connected["players"]
+        .as_array_mut()
+        .unwrap()
+        .push(json!({"team": 1, "slot": 1, "alias": "Player1", "name": "Player1"}));
+    let (mut client, mut peer) = connect(room);
+    catch_up(&mut client);
+
+    let player = client.player_by_name("Player2").unwrap();
+    assert_eq!(player.slot(), 2);
+    assert_eq!(client.player_by_name("Player1").unwrap().team(), 0);
+    assert!(client.player_by_name("Nobody").is_none());
+
+    peer.send(json!([{
+        "cmd": "RoomUpdate",
+        "players": [
+            {"team": 0, "slot": 1, "alias": "Player1", "name": "Player1"},
+            {"team": 0, "slot": 2, "alias": "Robin", "name": "Player2"},
+            {"team": 1, "slot": 1, "alias": "Player1", "name": "Player1"}
+        ]
+    }]));
+    update_until(&mut client, |event| {
+        matches!(event, Event::Updated(fields)
+            if fields.iter().any(|field| matches!(field, UpdatedField::Players(_))))
+    });
+
+    assert_eq!(client.player_by_alias("Robin").unwrap().slot(), 2);
+    assert!(client.player_by_alias("Player2").is_none());
+    // The original slot name still works after the alias changes.
+    assert_eq!(client.player_by_name("Player2").unwrap().alias(), "Robin");
+}
+
+#[test]
+fn multiworld_counts() {
+    let (mut client, _peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    // Test Game has three of each and Other Game has two of each. The
+    // Archipelago pseudo-game isn't included.
+    assert_eq!(client.multiworld_location_count(), 5);
+    assert_eq!(client.multiworld_item_count(), 5);
+}
+
+#[test]
+fn try_is_local_location_checked() {
+    let mut room = Room::default();
+    room.connected["missing_locations"] = json!([11, 12]);
+    room.connected["checked_locations"] = json!([10]);
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    assert_eq!(client.try_is_local_location_checked(10), Some(true));
+    assert_eq!(client.try_is_local_location_checked(11), Some(false));
+    // Other Game's location isn't in this world.
+    assert_eq!(client.try_is_local_location_checked(110), None);
+    assert!(client.is_local_location_checked(10));
+}
+
+/// Returns a room with a third player, `Player3`, who's also playing Test Game
+/// and who shares an item link group, `Link` (slot 4), with `Player1`.
+fn room_with_item_link() -> Room {
+    let mut room = Room::default();
+    let players = room.connected["players"].as_array_mut().unwrap();
+    players.push(json!({"team": 0, "slot": 3, "alias": "Player3", "name": "Player3"}));
+    players.push(json!({"team": 0, "slot": 4, "alias": "Link", "name": "Link"}));
+    room.connected["slot_info"]["3"] =
+        json!({"name": "Player3", "game": "Test Game", "type": 1, "group_members": []});
+    room.connected["slot_info"]["4"] =
+        json!({"name": "Link", "game": "Test Game", "type": 2, "group_members": [1, 3]});
+    room
+}
+
+#[test]
+fn games_with_player_counts() {
+    let (mut client, _peer) = connect(room_with_item_link());
+    catch_up(&mut client);
+
+    let mut counts = client
+        .games_with_player_counts()
+        .map(|(game, count)| (game.name().as_str(), count))
+        .collect::<Vec<_>>();
+    counts.sort();
+    // The item link group isn't counted as a player.
+    assert_eq!(counts, [("Other Game", 1), ("Test Game", 2)]);
+}
+
+#[test]
+fn my_name_groups() {
+    let mut room = Room::default();
+    room.data_packages["Test Game"]["item_name_groups"] = json!({"Weapons": ["Sword"]});
+    room.data_packages["Test Game"]["location_name_groups"] =
+        json!({"Chests": ["Chest 1", "Chest 2", "Chest 3"]});
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    let items = client.my_item_groups().collect::<Vec<_>>();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].0, "Weapons");
+    assert_eq!(items[0].1, ["Sword"]);
+
+    let locations = client.my_location_groups().collect::<Vec<_>>();
+    assert_eq!(locations.len(), 1);
+    assert_eq!(locations[0].0, "Chests");
+    assert_eq!(locations[0].1, ["Chest 1", "Chest 2", "Chest 3"]);
+}
+
+#[test]
+fn my_name_groups_without_groups() {
+    let (mut client, _peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    assert_eq!(client.my_item_groups().len(), 0);
+    assert_eq!(client.my_location_groups().len(), 0);
+}
+
+#[test]
+fn team_and_slot() {
+    let mut room = Room::default();
+    room.connected["team"] = json!(1);
+    room.connected["slot"] = json!(2);
+    room.connected["missing_locations"] = json!([110, 111]);
+    for player in room.connected["players"].as_array_mut().unwrap() {
+        player["team"] = json!(1);
+    }
+    let (mut client, _peer) = connect_with::<serde_json::Value>(
+        room,
+        "Player2",
+        Some("Other Game"),
+        ConnectionOptions::new().no_cache(),
+    );
+    catch_up(&mut client);
+
+    assert_eq!(client.team(), 1);
+    assert_eq!(client.slot(), 2);
+    assert_eq!(client.team(), client.this_player().team());
+    assert_eq!(client.slot(), client.this_player().slot());
+}
+
+/// Returns the name of the item and location for any item-like value.
+fn describe(item: impl AsRef<LocatedItem>) -> String {
+    let item = item.as_ref();
+    format!("{} at {}", item.item().name(), item.location().name())
+}
+
+#[test]
+fn received_item_conversions() {
+    let mut room = Room::default();
+    room.after_connected.push(json!({
+        "cmd": "ReceivedItems",
+        "index": 0,
+        "items": [{"item": 1, "location": 110, "player": 2, "flags": 1}]
+    }));
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    let received = client.received_items()[0].clone();
+    let located = LocatedItem::from(&received);
+    assert_eq!(located.item(), received.item());
+    assert_eq!(located.location(), received.location());
+    assert_eq!(located.sender().slot(), received.sender().slot());
+    assert_eq!(located.receiver().slot(), received.receiver().slot());
+
+    assert_eq!(describe(&received), "Sword at Cave");
+    assert_eq!(describe(received.clone()), "Sword at Cave");
+    assert_eq!(describe(&located), "Sword at Cave");
+
+    let owned: LocatedItem = received.into();
+    assert_eq!(describe(owned), "Sword at Cave");
+}
+
+#[test]
+fn game_for_slot() {
+    let mut room = room_with_item_link();
+    // A group that only appears in the slot info.
+    room.connected["slot_info"]["5"] =
+        json!({"name": "Other Link", "game": "Other Game", "type": 2, "group_members": [2]});
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    assert_eq!(client.game_for_slot(2).unwrap(), "Other Game");
+    assert_eq!(client.game_for_slot(4).unwrap(), "Test Game");
+    assert_eq!(client.game_for_slot(5).unwrap(), "Other Game");
+    assert_eq!(client.game_for_slot(0).unwrap(), "Archipelago");
+    assert!(client.game_for_slot(6).is_none());
+}
+
+#[test]
+fn room_games() {
+    let mut room = Room::default();
+    // The server doesn't have a data package for this game.
+    room.room_info["games"] = json!(["Test Game", "Other Game", "Third Game"]);
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    let mut room_games = client.room_games().collect::<Vec<_>>();
+    room_games.sort();
+    assert_eq!(room_games, ["Other Game", "Test Game", "Third Game"]);
+
+    let third = client.game("Third Game").unwrap();
+    assert!(!third.has_data_package());
+    let mut games = client
+        .games()
+        .filter(|game| game.has_data_package())
+        .map(|game| game.name().as_str())
+        .collect::<Vec<_>>();
+    games.sort();
+    assert_eq!(games, ["Other Game", "Test Game"]);
+}
+
+#[test]
+fn my_item_links() {
+    let mut room = room_with_item_link();
+    // A second group that the local player isn't in.
+    room.connected["players"]
+        .as_array_mut()
+        .unwrap()
+        .push(json!({"team": 0, "slot": 5, "alias": "Other Link", "name": "Other Link"}));
+    room.connected["slot_info"]["5"] =
+        json!({"name": "Other Link", "game": "Test Game", "type": 2, "group_members": [3]});
+    let (mut client, _peer) = connect(room);
+    catch_up(&mut client);
+
+    let links = client.my_item_links().collect::<Vec<_>>();
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].slot(), 4);
+    let mut members = links[0]
+        .group_members()
+        .iter()
+        .map(|member| member.slot())
+        .collect::<Vec<_>>();
+    members.sort();
+    assert_eq!(members, [1, 3]);
+}
+
+/// Slot data that's changed format between versions of an apworld.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum VersionedSlotData {
+    V2(SlotDataV2),
+    V1(SlotDataV1),
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct SlotDataV2 {
+    goal: String,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct SlotDataV1 {
+    goal_id: u32,
+}
+
+/// Decodes slot data as [SlotDataV2], falling back to [SlotDataV1].
+fn decode_versioned_slot_data(value: &serde_json::Value) -> Result<VersionedSlotData, Error> {
+    SlotDataV2::deserialize(value)
+        .map(VersionedSlotData::V2)
+        .or_else(|_| SlotDataV1::deserialize(value).map(VersionedSlotData::V1))
+        .map_err(|error| {
+            ProtocolError::Deserialize {
+                json: value.to_string(),
+                error,
+            }
+            .into()
+        })
+}
+
+/// Connects to a server whose slot data is `slot_data` using
+/// [decode_versioned_slot_data].
+fn connect_versioned(slot_data: serde_json::Value) -> Result<Client<VersionedSlotData>, Error> {
+    let mut room = Room::default();
+    room.connected["slot_data"] = slot_data;
+    let server = Server::start(room);
+    let client = smol::block_on(Client::connect_with_slot_data_decoder(
+        server.url.clone(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().no_cache(),
+        decode_versioned_slot_data,
+    ))?;
+    server.accept().answer_race_mode(0);
+    Ok(client)
+}
+
+#[test]
+fn slot_data_decoder_prefers_newer_schema() {
+    let client = connect_versioned(json!({"goal": "Boss"})).unwrap();
+    assert_eq!(
+        *client.slot_data(),
+        VersionedSlotData::V2(SlotDataV2 {
+            goal: "Boss".into()
+        })
+    );
+}
+
+#[test]
+fn slot_data_decoder_falls_back_to_older_schema() {
+    let client = connect_versioned(json!({"goal_id": 3})).unwrap();
+    assert_eq!(
+        *client.slot_data(),
+        VersionedSlotData::V1(SlotDataV1 { goal_id: 3 })
+    );
+}
+
+#[test]
+fn slot_data_decoder_error() {
+    let Err(err) = connect_versioned(json!({"unknown": true})) else {
+        panic!("expected slot data decoding to fail");
+    };
+    assert!(matches!(
+        err,
+        Error::ProtocolError(ProtocolError::Deserialize { .. })
+    ));
+}
+
+#[test]
+fn plain_transport() {
+    let (client, _peer) = connect(Room::default());
+    assert_eq!(client.transport(), Transport::Plain);
+    assert!(!client.transport().is_tls());
+}
+
+#[test]
+fn received_items_in_connected_frame() {
+    let mut room = Room::default();
+    room.after_connected.push(json!({
+        "cmd": "ReceivedItems",
+        "index": 0,
+        "items": [
+            {"item": 1, "location": 110, "player": 2, "flags": 0},
+            {"item": 2, "location": 111, "player": 2, "flags": 0}
+        ]
+    }));
+    let (mut client, _peer) = connect(room);
+
+    let events = catch_up(&mut client);
+    let received = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::ReceivedItems(index) => Some(*index),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(received, [0]);
+    let names = client
+        .received_items()
+        .iter()
+        .map(|item| item.item().name().as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Sword", "Shield"]);
+}
+
+#[test]
+fn catch_up_complete_after_resync() {
+    // A reconnecting client gets every item it's ever received replayed
+    // right after Connected.
+    let mut room = Room::default();
+    room.after_connected.push(received_items(0, &[1, 2]));
+    let (mut client, mut peer) = connect(room);
+
+    let events = catch_up(&mut client);
+    let [.., Event::ReceivedItems(0), Event::CatchUpComplete] = events.as_slice() else {
+        panic!("expected the replay right before CatchUpComplete");
+    };
+
+    // Later items are live and don't complete catching up again.
+    peer.send(json!([received_items(2, &[3])]));
+    let mut events = update_until(&mut client, |event| {
+        matches!(event, Event::ReceivedItems(_))
+    });
+    events.extend(client.update());
+    assert!(
+        !events
+            .iter()
+            .any(|event| matches!(event, Event::CatchUpComplete))
+    );
+}
+
+#[test]
+fn room_update_cant_change_local_game() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    // RoomUpdate has no field that carries a slot's game, so a server that
+    // tries to reassign the local slot's game this way is ignored.
+    peer.send(json!([{
+        "cmd": "RoomUpdate",
+        "players": [
+            {"team": 0, "slot": 1, "alias": "Renamed", "name": "Player1"},
+            {"team": 0, "slot": 2, "alias": "Player2", "name": "Player2"}
+        ],
+        "slot_info": {
+            "1": {"name": "Player1", "game": "Other Game", "type": 1, "group_members": []}
+        },
+        "checked_locations": [10]
+    }]));
+    update_until(&mut client, |event| matches!(event, Event::Updated(_)));
+
+    assert_eq!(client.this_player().alias(), "Renamed");
+    assert_eq!(client.this_game().name(), "Test Game");
+    assert_eq!(client.game_for_slot(1).unwrap(), "Test Game");
+    let checked = client
+        .checked_locations()
+        .map(|location| location.name().as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(checked, ["Chest 1"]);
+}
+
+/// Returns a `ReceivedItems` message for `items`, which are Test Game item IDs
+/// found in Player2's cave, starting at `index`.
+fn received_items(index: usize, items: &[i64]) -> serde_json::Value {
+    json!({
+        "cmd": "ReceivedItems",
+        "index": index,
+        "items": items
+            .iter()
+            .map(|item| json!({"item": item, "location": 110, "player": 2, "flags": 0}))
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Returns the names of the items `client` has received.
+fn received_item_names(client: &Client) -> Vec<&str> {
+    client
+        .received_items()
+        .iter()
+        .map(|item| item.item().name().as_str())
+        .collect()
+}
+
+#[test]
+fn last_received_index() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+    assert_eq!(client.last_received_index(), None);
+
+    peer.send(json!([received_items(0, &[1, 2])]));
+    update_until(&mut client, |event| {
+        matches!(event, Event::ReceivedItems(_))
+    });
+    assert_eq!(client.last_received_index(), Some(2));
+
+    peer.send(json!([received_items(2, &[3])]));
+    update_until(&mut client, |event| {
+        matches!(event, Event::ReceivedItems(_))
+    });
+    assert_eq!(client.last_received_index(), Some(3));
+    assert_eq!(received_item_names(&client), ["Sword", "Shield", "Potion"]);
+}
+
+/// Reconnects to a server that replays Sword, Shield, and Potion, skipping
+/// the first `last_received_index` items, and returns the indexes of the
+/// [Event::ReceivedItems] emitted while catching up.
+fn replayed_indexes(last_received_index: usize) -> Vec<usize> {
+    let mut room = Room::default();
+    room.after_connected.push(received_items(0, &[1, 2, 3]));
+    let (mut client, _peer) = connect_with::<serde_json::Value>(
+        room,
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new()
+            .no_cache()
+            .skip_initial_item_replay(last_received_index),
+    );
+    let events = catch_up(&mut client);
+    assert_eq!(client.received_items().len(), 3);
+    assert_eq!(client.last_received_index(), Some(3));
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Event::ReceivedItems(index) => Some(*index),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn skip_initial_item_replay() {
+    assert_eq!(replayed_indexes(2), [2]);
+}
+
+#[test]
+fn skip_initial_item_replay_without_new_items() {
+    assert!(replayed_indexes(3).is_empty());
+}
+
+#[test]
+fn skip_initial_item_replay_from_another_session() {
+    assert_eq!(replayed_indexes(5), [0]);
+}
+
+#[test]
+fn unsolicited_resync() {
+    let mut room = Room::default();
+    room.after_connected.push(received_items(0, &[1]));
+    let (mut client, mut peer) = connect(room);
+    catch_up(&mut client);
+    assert_eq!(received_item_names(&client), ["Sword"]);
+
+    peer.send(json!([received_items(0, &[2, 3])]));
+    let events = update_until(&mut client, |event| {
+        matches!(event, Event::ReceivedItems(_))
+    });
+    let [.., Event::ItemsResynced, Event::ReceivedItems(0)] = events.as_slice() else {
+        panic!("expected ItemsResynced followed by ReceivedItems");
+    };
+    assert_eq!(received_item_names(&client), ["Shield", "Potion"]);
+}
+
+#[test]
+fn requested_resync() {
+    let mut room = Room::default();
+    room.after_connected.push(received_items(0, &[1]));
+    let (mut client, mut peer) = connect(room);
+    catch_up(&mut client);
+
+    client.sync().unwrap();
+    peer.expect("Sync");
+    peer.send(json!([received_items(0, &[1, 2])]));
+    let events = update_until(&mut client, |event| {
+        matches!(event, Event::ReceivedItems(_))
+    });
+    assert!(
+        !events
+            .iter()
+            .any(|event| matches!(event, Event::ItemsResynced))
+    );
+    assert_eq!(received_item_names(&client), ["Sword", "Shield"]);
+}
+
+#[test]
+fn player_renamed() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    peer.send(json!([{
+        "cmd": "RoomUpdate",
+        "players": [
+            {"team": 0, "slot": 1, "alias": "Player1", "name": "Player1"},
+            {"team": 0, "slot": 2, "alias": "Nat", "name": "Player2"}
+        ]
+    }]));
+    let events = update_until(&mut client, |event| {
+        matches!(event, Event::PlayerRenamed { .. })
+    });
+    let [
+        ..,
+        Event::Updated(_),
+        Event::PlayerRenamed { player, old_alias },
+    ] = events.as_slice()
+    else {
+        panic!("expected PlayerRenamed right after Updated");
+    };
+    assert_eq!(player.slot(), 2);
+    assert_eq!(player.alias(), "Nat");
+    assert_eq!(old_alias, "Player2");
+
+    // A RoomUpdate that doesn't change any aliases doesn't rename anyone.
+    peer.send(json!([{"cmd": "RoomUpdate", "hint_points": 3}]));
+    let mut events = update_until(&mut client, |event| matches!(event, Event::Updated(_)));
+    events.extend(client.update());
+    assert!(
+        !events
+            .iter()
+            .any(|event| matches!(event, Event::PlayerRenamed { .. }))
+    );
+}
+
+#[test]
+fn players_added() {
+    // The server knows about slot 3 from the start, but doesn't list it as a
+    // player until a later RoomUpdate.
+    let mut room = Room::default();
+    room.connected["slot_info"]["3"] = json!({
+        "name": "Player3",
+        "game": "Test Game",
+        "type": 1,
+        "group_members": []
+    });
+    let (mut client, mut peer) = connect(room);
+    catch_up(&mut client);
+    let before = client.players().count();
+
+    peer.send(json!([{
+        "cmd": "RoomUpdate",
+        "players": [
+            {"team": 0, "slot": 1, "alias": "Player1", "name": "Player1"},
+            {"team": 0, "slot": 2, "alias": "Player2", "name": "Player2"},
+            {"team": 0, "slot": 3, "alias": "Newcomer", "name": "Player3"}
+        ]
+    }]));
+    let events = update_until(&mut client, |event| matches!(event, Event::Updated(_)));
+    let Some(Event::Updated(fields)) = events.last() else {
+        unreachable!();
+    };
+    let Some(added) = fields.iter().find_map(|field| match field {
+        UpdatedField::PlayersAdded(added) => Some(added),
+        _ => None,
+    }) else {
+        panic!("expected PlayersAdded");
+    };
+    let [player] = added.as_slice() else {
+        panic!("expected one new player");
+    };
+    assert_eq!(player.slot(), 3);
+    assert_eq!(player.alias(), "Newcomer");
+    assert_eq!(player.game(), "Test Game");
+    assert_eq!(client.players().count(), before + 1);
+}
+
+#[test]
+fn on_event_callbacks() {
+    let mut room = Room::default();
+    room.after_connected.push(received_items(0, &[1]));
+    let (mut client, _peer) = connect(room);
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    for name in ["first", "second"] {
+        let calls = Arc::clone(&calls);
+        client.on_event(move |event| {
+            let kind = match event {
+                Event::ReceivedItems(_) => "ReceivedItems",
+                Event::CatchUpComplete => "CatchUpComplete",
+                _ => "other",
+            };
+            calls.lock().unwrap().push((name, kind));
+        });
+    }
+
+    let events = catch_up(&mut client);
+    let calls = calls.lock().unwrap();
+    // Each callback is called once for every event, in registration order.
+    assert_eq!(calls.len(), events.len() * 2);
+    for pair in calls.chunks(2) {
+        assert_eq!(pair[0].0, "first");
+        assert_eq!(pair[1].0, "second");
+        assert_eq!(pair[0].1, pair[1].1);
+    }
+    assert!(calls.contains(&("first", "ReceivedItems")));
+    assert_eq!(calls.last(), Some(&("second", "CatchUpComplete")));
+}
+
+/// Returns the current local time in seconds since the Unix epoch.
+fn now_seconds() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64()
+}
+
+/// Returns a `Bounced` death link message sent at `time` seconds since the
+/// Unix epoch according to the server's clock.
+fn death_link(time: f64) -> serde_json::Value {
+    json!({
+        "cmd": "Bounced",
+        "tags": ["DeathLink"],
+        "data": {"time": time, "cause": "Player2 fell", "source": "Player2"}
+    })
+}
+
+/// Returns a room whose server clock is `skew` seconds behind the local clock.
+fn room_with_skew(skew: f64) -> Room {
+    let mut room = Room::default();
+    room.room_info["time"] = json!(now_seconds() - skew);
+    room
+}
+
+#[test]
+fn death_link_time_ago() {
+    let skew = 100.0;
+    let (mut client, mut peer) = connect(room_with_skew(skew));
+    catch_up(&mut client);
+
+    // The death occurred five seconds ago according to the server's clock.
+    peer.send(json!([death_link(now_seconds() - skew - 5.0)]));
+    let events = update_until(&mut client, |event| {
+        matches!(event, Event::DeathLink { .. })
+    });
+    let Some(Event::DeathLink { time_ago, .. }) = events.last() else {
+        unreachable!();
+    };
+    let time_ago = time_ago.unwrap();
+    assert!(
+        time_ago >= Duration::from_secs(4) && time_ago < Duration::from_secs(10),
+        "{time_ago:?}"
+    );
+}
+
+#[test]
+fn death_link_time_ago_in_future() {
+    let skew = 100.0;
+    let (mut client, mut peer) = connect(room_with_skew(skew));
+    catch_up(&mut client);
+
+    peer.send(json!([death_link(now_seconds() - skew + 60.0)]));
+    let events = update_until(&mut client, |event| {
+        matches!(event, Event::DeathLink { .. })
+    });
+    let Some(Event::DeathLink { time_ago, .. }) = events.last() else {
+        unreachable!();
+    };
+    assert_eq!(*time_ago, None);
+}
+
+/// Sends two death links to `client` in quick succession and returns how many
+/// [Event::DeathLink]s it emits.
+fn count_death_links(options: ConnectionOptions) -> usize {
+    let (mut client, mut peer) = connect_with::<serde_json::Value>(
+        Room::default(),
+        "Player1",
+        Some("Test Game"),
+        options.no_cache(),
+    );
+    catch_up(&mut client);
+
+    let time = now_seconds();
+    peer.send(json!([
+        death_link(time),
+        death_link(time),
+        // A final bounce to tell when the client is done with the death links.
+        {"cmd": "Bounced", "tags": ["Done"], "data": {}}
+    ]));
+    update_until(&mut client, |event| matches!(event, Event::Bounce { .. }))
+        .iter()
+        .filter(|event| matches!(event, Event::DeathLink { .. }))
+        .count()
+}
+
+#[test]
+fn death_link_dedup() {
+    let options = ConnectionOptions::new().death_link_dedup(Duration::from_secs(60));
+    assert_eq!(count_death_links(options), 1);
+}
+
+#[test]
+fn death_link_without_dedup() {
+    assert_eq!(count_death_links(ConnectionOptions::new()), 2);
+}
+
+#[test]
+fn downloaded_checksum_mismatch() {
+    let mut room = Room::default();
+    room.data_packages["Other Game"]["checksum"] = json!("corrupted");
+    let server = Server::start(room);
+    let result = smol::block_on(Client::<serde_json::Value>::connect(
+        server.url.clone(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().no_cache(),
+    ));
+    let Err(Error::ProtocolError(ProtocolError::ChecksumMismatch {
+        game,
+        expected,
+        actual,
+    })) = result
+    else {
+        panic!("expected a checksum mismatch");
+    };
+    assert_eq!(game, "Other Game");
+    assert_eq!(expected, "other-checksum");
+    assert_eq!(actual, "corrupted");
+}
+
+#[test]
+fn refreshed_checksum_mismatch() {
+    let (mut client, mut peer) = connect(Room::default());
+    catch_up(&mut client);
+
+    let mut room = Room::default();
+    room.data_packages["Test Game"]["checksum"] = json!("corrupted");
+    client
+        .refresh_data_package(Some(vec!["Test Game".into()]))
+        .unwrap();
+    let request = peer.expect("GetDataPackage");
+    peer.send_data_packages(&room, request);
+    let events = update_until(&mut client, |event| matches!(event, Event::Error(_)));
+    assert!(matches!(
+        events.last(),
+        Some(Event::Error(Error::ProtocolError(
+            ProtocolError::ChecksumMismatch { .. }
+        )))
+    ));
+    // The client keeps using the data it already had.
+    assert_eq!(client.this_game().assert_item(1).name(), "Sword");
+}
@@ -0,0 +1,342 @@
+//! A scripted Archipelago server for driving clients in integration tests.
+
+// Each test file uses a different subset of these helpers.
+#![allow(dead_code)]
+
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use archipelago_rs::{Client, ConnectionOptions, Event};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+use tungstenite::{Message, WebSocket};
+
+/// The room a [Server] describes to the clients that connect to it.
+///
+/// The default room has two players: `Player1` (slot 1) playing `Test Game`,
+/// and `Player2` (slot 2) playing `Other Game`. Tests can edit the raw JSON
+/// fields to describe other rooms.
+#[derive(Clone)]
+pub struct Room {
+    /// The `RoomInfo` message.
+    pub room_info: Value,
+
+    /// The data packages, keyed by game name.
+    pub data_packages: Map<String, Value>,
+
+    /// The `Connected` message.
+    pub connected: Value,
+
+    /// Additional messages to send in the same frame as `Connected`.
+    pub after_connected: Vec<Value>,
+
+    /// The value of the `_read_race_mode` data storage key.
+    pub race_mode: u8,
+}
+
+impl Default for Room {
+    fn default() -> Self {
+        let mut data_packages = Map::new();
+        data_packages.insert(
+            "Test Game".into(),
+            json!({
+                "item_name_to_id": {"Sword": 1, "Shield": 2, "Potion": 3},
+                "location_name_to_id": {"Chest 1": 10, "Chest 2": 11, "Chest 3": 12},
+                "checksum": "test-checksum"
+            }),
+        );
+        data_packages.insert(
+            "Other Game".into(),
+            json!({
+                "item_name_to_id": {"Bow": 101, "Arrow": 102},
+                "location_name_to_id": {"Cave": 110, "Tower": 111},
+                "checksum": "other-checksum"
+            }),
+        );
+
+        Room {
+            room_info: json!({
+                "cmd": "RoomInfo",
+                "version": {"major": 0, "minor": 6, "build": 0, "class": "Version"},
+                "generator_version": {"major": 0, "minor": 6, "build": 0, "class": "Version"},
+                "tags": ["AP"],
+                "password": false,
+                "permissions": {"release": 1, "collect": 1, "remaining": 1},
+                "hint_cost": 10,
+                "location_check_points": 1,
+                "games": ["Test Game", "Other Game"],
+                "datapackage_checksums": {
+                    "Test Game": "test-checksum",
+                    "Other Game": "other-checksum"
+                },
+                "seed_name": "seed",
+                "time": 0.0
+            }),
+            data_packages,
+            connected: json!({
+                "cmd": "Connected",
+                "team": 0,
+                "slot": 1,
+                "players": [
+                    {"team": 0, "slot": 1, "alias": "Player1", "name": "Player1"},
+                    {"team": 0, "slot": 2, "alias": "Player2", "name": "Player2"}
+                ],
+                "missing_locations": [10, 11, 12],
+                "checked_locations": [],
+                "slot_data": {},
+                "slot_info": {
+                    "1": {"name": "Player1", "game": "Test Game", "type": 1, "group_members": []},
+                    "2": {"name": "Player2", "game": "Other Game", "type": 1, "group_members": []}
+                },
+                "hint_points": 0
+            }),
+            after_connected: Vec::new(),
+            race_mode: 0,
+        }
+    }
+}
+
+/// A local WebSocket server that plays the server side of the Archipelago
+/// protocol.
+pub struct Server {
+    /// The `ws://` URL clients can use to connect to this server.
+    pub url: String,
+
+    /// Produces a [Peer] for each connection the server accepts.
+    peers: mpsc::Receiver<Peer>,
+}
+
+impl Server {
+    /// Starts a server that runs the handshake for `room` with every client
+    /// that connects.
+    pub fn start(room: Room) -> Server {
+        Self::spawn(Some(room))
+    }
+
+    /// Starts a server that completes the WebSocket handshake but leaves the
+    /// Archipelago handshake up to the test.
+    pub fn start_raw() -> Server {
+        Self::spawn(None)
+    }
+
+    fn spawn(room: Option<Room>) -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (sender, peers) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut peer = Peer::accept(stream.unwrap());
+                if let Some(room) = &room {
+                    peer.handshake(room);
+                }
+                if sender.send(peer).is_err() {
+                    return;
+                }
+            }
+        });
+        Server { url, peers }
+    }
+
+    /// Returns the next connection the server accepted.
+    pub fn accept(&self) -> Peer {
+        self.peers
+            .recv_timeout(Duration::from_secs(10))
+            .expect("timed out waiting for a connection")
+    }
+}
+
+/// The server side of a single client connection.
+pub struct Peer {
+    socket: WebSocket<TcpStream>,
+
+    /// The `Connect` message the client sent, if the handshake got that far.
+    pub connect: Option<Value>,
+
+    /// The `GetDataPackage` messages the client sent during the handshake.
+    pub data_package_requests: Vec<Value>,
+}
+
+impl Peer {
+    fn accept(stream: TcpStream) -> Peer {
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        Peer {
+            socket: tungstenite::accept(stream).unwrap(),
+            connect: None,
+            data_package_requests: Vec::new(),
+        }
+    }
+
+    /// Runs the server side of the Archipelago handshake for `room`.
+    pub fn handshake(&mut self, room: &Room) {
+        self.send(json!([room.room_info]));
+        loop {
+            for message in self.recv() {
+                match message["cmd"].as_str() {
+                    Some("GetDataPackage") => self.send_data_packages(room, message),
+                    Some("Connect") => {
+                        self.connect = Some(message);
+                        let mut frame = vec![room.connected.clone()];
+                        frame.extend(room.after_connected.iter().cloned());
+                        self.send(Value::Array(frame));
+                        return;
+                    }
+                    cmd => panic!("unexpected message during handshake: {cmd:?}"),
+                }
+            }
+        }
+    }
+
+    /// Answers the `GetDataPackage` message `request` with data from `room`.
+    pub fn send_data_packages(&mut self, room: &Room, request: Value) {
+        let games = match request["games"].as_array() {
+            Some(games) => games
+                .iter()
+                .filter_map(|game| {
+                    let game = game.as_str().unwrap();
+                    let data = room.data_packages.get(game)?;
+                    Some((game.to_string(), data.clone()))
+                })
+                .collect(),
+            None => room.data_packages.clone(),
+        };
+        self.data_package_requests.push(request);
+        self.send(json!([{"cmd": "DataPackage", "data": {"games": games}}]));
+    }
+
+    /// Sends `messages`, which must be a JSON array, as a single frame.
+    pub fn send(&mut self, messages: Value) {
+        assert!(messages.is_array());
+        self.socket
+            .send(Message::text(messages.to_string()))
+            .unwrap();
+    }
+
+    /// Closes the connection from the server side.
+    pub fn close(&mut self) {
+        self.socket.close(None).unwrap();
+        self.socket.flush().unwrap();
+    }
+
+    /// Returns the messages in the next text frame the client sends.
+    pub fn recv(&mut self) -> Vec<Value> {
+        loop {
+            if let Message::Text(text) = self.socket.read().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    /// Receives the next frame and asserts that it contains exactly one
+    /// message, whose command is `cmd`.
+    pub fn expect(&mut self, cmd: &str) -> Value {
+        let mut messages = self.recv();
+        assert_eq!(messages.len(), 1, "expected one message, got {messages:?}");
+        let message = messages.remove(0);
+        assert_eq!(message["cmd"], cmd, "unexpected message {message}");
+        message
+    }
+
+    /// Asserts that the client doesn't send anything for a short time.
+    pub fn assert_no_message(&mut self) {
+        let stream = self.socket.get_ref();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        match self.socket.read() {
+            Err(tungstenite::Error::Io(err))
+                if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            result => panic!("expected no message, got {result:?}"),
+        }
+        self.socket
+            .get_ref()
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+    }
+
+    /// Answers the race mode request the client sends after it connects.
+    pub fn answer_race_mode(&mut self, race_mode: u8) {
+        let get = self.expect("Get");
+        assert_eq!(get["keys"], json!(["_read_race_mode"]));
+        self.send(json!([{"cmd": "Retrieved", "keys": {"_read_race_mode": race_mode}}]));
+    }
+}
+
+/// Connects a client to a server running `room` as `Player1` playing
+/// `Test Game`, and answers the client's race mode request.
+pub fn connect(room: Room) -> (Client, Peer) {
+    connect_with(
+        room,
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().no_cache(),
+    )
+}
+
+/// Like [connect], but with a custom slot name, game, and options.
+pub fn connect_with<S: DeserializeOwned + 'static>(
+    room: Room,
+    name: &str,
+    game: Option<&str>,
+    options: ConnectionOptions,
+) -> (Client<S>, Peer) {
+    let race_mode = room.race_mode;
+    let server = Server::start(room);
+    let client = smol::block_on(Client::connect(server.url.clone(), name, game, options)).unwrap();
+    let mut peer = server.accept();
+    peer.answer_race_mode(race_mode);
+    (client, peer)
+}
+
+/// Updates `client` until it emits an event that matches `predicate`, and
+/// returns all the events it emitted up to and including that one.
+pub fn update_until<S: DeserializeOwned + 'static>(
+    client: &mut Client<S>,
+    mut predicate: impl FnMut(&Event) -> bool,
+) -> Vec<Event> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut events = Vec::new();
+    loop {
+        for event in client.update() {
+            let matched = predicate(&event);
+            events.push(event);
+            if matched {
+                return events;
+            }
+        }
+        assert!(
+            Instant::now() < deadline,
+            "timed out waiting for event ({} others emitted)",
+            events.len()
+        );
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Updates `client` until `receiver` produces a value, and returns it.
+pub fn resolve<S: DeserializeOwned + 'static, T>(
+    client: &mut Client<S>,
+    receiver: oneshot::Receiver<T>,
+) -> T {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        client.update();
+        match receiver.try_recv() {
+            Ok(value) => return value,
+            Err(oneshot::TryRecvError::Empty) => {}
+            Err(oneshot::TryRecvError::Disconnected) => panic!("request was dropped"),
+        }
+        assert!(Instant::now() < deadline, "timed out waiting for response");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Updates `client` until it's finished catching up with the server after
+/// connecting, and returns all the events it emitted.
+pub fn catch_up<S: DeserializeOwned + 'static>(client: &mut Client<S>) -> Vec<Event> {
+    update_until(client, |event| matches!(event, Event::CatchUpComplete))
+}
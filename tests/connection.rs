@@ -0,0 +1,461 @@
+use std::mem;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use archipelago_rs::{
+    ArgumentError, Cache, Client, ConnectingPhase, Connection, ConnectionError, ConnectionOptions,
+    ConnectionState, Error,
+};
+use serde_json::{Value, json};
+use tungstenite::Message;
+use ustr::ustr;
+
+mod common;
+
+use common::{Room, Server, catch_up};
+
+/// Returns a `ws://` URL for a local port that nothing is listening on.
+fn dead_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+    format!("ws://127.0.0.1:{port}")
+}
+
+/// Starts a server that refuses every connection with `InvalidPassword`.
+///
+/// Returns the server's URL along with a receiver that produces the password
+/// each connection attempt sent.
+fn refusing_server() -> (String, mpsc::Receiver<Option<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let password = refuse(stream.unwrap());
+            if sender.send(password).is_err() {
+                return;
+            }
+        }
+    });
+    (url, receiver)
+}
+
+/// Runs the server side of a single connection attempt, refusing it once the
+/// client sends `Connect`. Returns the password the client sent.
+fn refuse(stream: TcpStream) -> Option<String> {
+    let mut socket = tungstenite::accept(stream).unwrap();
+    socket
+        .send(Message::text(
+            json!([{
+                "cmd": "RoomInfo",
+                "version": {"major": 0, "minor": 6, "build": 0, "class": "Version"},
+                "generator_version": {"major": 0, "minor": 6, "build": 0, "class": "Version"},
+                "tags": ["AP"],
+                "password": true,
+                "permissions": {"release": 1, "collect": 1, "remaining": 1},
+                "hint_cost": 10,
+                "location_check_points": 1,
+                "games": [],
+                "datapackage_checksums": {},
+                "seed_name": "seed",
+                "time": 0.0
+            }])
+            .to_string(),
+        ))
+        .unwrap();
+
+    loop {
+        let Message::Text(text) = socket.read().unwrap() else {
+            continue;
+        };
+        let messages: Vec<Value> = serde_json::from_str(&text).unwrap();
+        let Some(connect) = messages.into_iter().find(|m| m["cmd"] == "Connect") else {
+            continue;
+        };
+
+        socket
+            .send(Message::text(
+                json!([{"cmd": "ConnectionRefused", "errors": ["InvalidPassword"]}]).to_string(),
+            ))
+            .unwrap();
+        return connect["password"].as_str().map(|p| p.to_string());
+    }
+}
+
+/// Updates `connection` until it's no longer connecting.
+fn wait_for_disconnect(connection: &mut Connection) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while matches!(connection.state(), ConnectionState::Connecting(_)) {
+        assert!(
+            Instant::now() < deadline,
+            "timed out waiting for connection"
+        );
+        connection.update();
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn retry_with_password_tries_fallback_urls() {
+    let (url, passwords) = refusing_server();
+    let mut connection = Connection::new_with_fallbacks(
+        [dead_url(), url],
+        "Player",
+        Some("Game"),
+        ConnectionOptions::new().no_cache(),
+    );
+
+    wait_for_disconnect(&mut connection);
+    assert!(matches!(
+        connection.error(),
+        Some(Error::ConnectionRefused(errors))
+            if matches!(errors.as_slice(), [ConnectionError::InvalidPassword])
+    ));
+    assert_eq!(passwords.recv().unwrap(), None);
+
+    connection.retry_with_password("hunter2");
+    wait_for_disconnect(&mut connection);
+    assert!(matches!(
+        connection.error(),
+        Some(Error::ConnectionRefused(_))
+    ));
+    assert_eq!(
+        passwords.recv_timeout(Duration::from_secs(1)).unwrap(),
+        Some("hunter2".into())
+    );
+}
+
+/// Updates `connection` until `receiver` produces a value, and returns it.
+fn update_until_received<T>(connection: &mut Connection, receiver: &mpsc::Receiver<T>) -> T {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        connection.update();
+        if let Ok(value) = receiver.try_recv() {
+            return value;
+        }
+        assert!(Instant::now() < deadline, "timed out waiting for server");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn downloads_data_packages_one_at_a_time() {
+    let server = Server::start_raw();
+    let url = server.url.clone();
+    let (requested, requests) = mpsc::channel();
+    let (answer, answers) = mpsc::channel();
+    let server_thread = thread::spawn(move || {
+        let room = Room::default();
+        let mut peer = server.accept();
+        peer.send(json!([room.room_info]));
+        for _ in 0..2 {
+            let request = peer.expect("GetDataPackage");
+            // The client shouldn't request the next package until this one
+            // arrives.
+            peer.assert_no_message();
+            requested.send(request["games"].clone()).unwrap();
+            answers.recv().unwrap();
+            peer.send_data_packages(&room, request);
+        }
+        peer.expect("Connect");
+        peer.send(json!([room.connected]));
+        peer.answer_race_mode(0);
+        // Keep the connection open until the client has seen that it's
+        // connected.
+        peer
+    });
+
+    let mut connection = Connection::new(
+        url,
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().no_cache(),
+    );
+    let mut downloaded = Vec::new();
+    for index in 0..2 {
+        let games = update_until_received(&mut connection, &requests);
+        let [game] = games.as_array().unwrap().as_slice() else {
+            panic!("expected a single game, got {games}");
+        };
+        let game = ustr(game.as_str().unwrap());
+        assert_eq!(
+            connection.connecting_phase(),
+            Some(ConnectingPhase::DataPackage {
+                game,
+                index,
+                total: 2
+            })
+        );
+        downloaded.push(game);
+        answer.send(()).unwrap();
+    }
+    downloaded.sort();
+    assert_eq!(downloaded, [ustr("Other Game"), ustr("Test Game")]);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !connection.is_connected() {
+        assert!(
+            Instant::now() < deadline,
+            "timed out waiting for connection"
+        );
+        connection.update();
+        thread::sleep(Duration::from_millis(5));
+    }
+    mem::drop(server_thread.join().unwrap());
+}
+
+#[test]
+fn unresolvable_host_is_dns_failure() {
+    let result = smol::block_on(Client::<Value>::connect(
+        "ws://archipelago-rs-test.invalid",
+        "Player",
+        Some("Game"),
+        ConnectionOptions::new().no_cache(),
+    ));
+    let Err(err) = result else {
+        panic!("expected connection to fail");
+    };
+    assert!(err.is_dns_failure(), "{err:?}");
+}
+
+#[test]
+fn new_with_fallbacks_without_urls() {
+    let mut connection = Connection::<Value>::new_with_fallbacks(
+        Vec::<String>::new(),
+        "Player",
+        Some("Game"),
+        ConnectionOptions::new().no_cache(),
+    );
+    connection.update();
+    assert!(matches!(
+        connection.error(),
+        Some(Error::ArgumentError(ArgumentError::NoUrls))
+    ));
+
+    connection.retry_with_password("hunter2");
+    assert!(matches!(
+        connection.error(),
+        Some(Error::ArgumentError(ArgumentError::NoUrls))
+    ));
+}
+
+#[test]
+fn retry_with_password_without_urls() {
+    let mut connection = Connection::<Value>::default();
+    connection.retry_with_password("hunter2");
+    assert!(matches!(
+        connection.error(),
+        Some(Error::ArgumentError(ArgumentError::NoUrls))
+    ));
+}
+
+#[test]
+fn fallback_resets_connecting_phase() {
+    // The first server accepts the WebSocket connection and then drops it
+    // before sending RoomInfo.
+    let first = TcpListener::bind("127.0.0.1:0").unwrap();
+    let first_url = format!("ws://{}", first.local_addr().unwrap());
+    thread::spawn(move || {
+        let (stream, _) = first.accept().unwrap();
+        drop(tungstenite::accept(stream).unwrap());
+    });
+
+    // The second server accepts the TCP connection but never completes the
+    // WebSocket handshake.
+    let second = TcpListener::bind("127.0.0.1:0").unwrap();
+    let second_url = format!("ws://{}", second.local_addr().unwrap());
+    let (accepted, wait_for_accept) = mpsc::channel();
+    thread::spawn(move || {
+        let (stream, _) = second.accept().unwrap();
+        accepted.send(()).unwrap();
+        thread::sleep(Duration::from_secs(10));
+        drop(stream);
+    });
+
+    let mut connection = Connection::<Value>::new_with_fallbacks(
+        [first_url, second_url],
+        "Player",
+        Some("Game"),
+        ConnectionOptions::new().no_cache(),
+    );
+    update_until_received(&mut connection, &wait_for_accept);
+    assert_eq!(
+        connection.connecting_phase(),
+        Some(ConnectingPhase::OpeningSocket)
+    );
+}
+
+#[test]
+fn shared_across_threads() {
+    let server = Server::start(Room::default());
+    let connection = Arc::new(Mutex::new(Connection::<Value>::new(
+        server.url.clone(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().no_cache(),
+    )));
+
+    // A reader thread waits for the updating thread to connect, then reads
+    // from the client through the shared handle.
+    let reader = thread::spawn({
+        let connection = Arc::clone(&connection);
+        move || {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            loop {
+                if let Some(client) = connection.lock().unwrap().client() {
+                    return (client.this_player().name(), client.this_game().name());
+                }
+                assert!(Instant::now() < deadline, "timed out waiting for client");
+                thread::sleep(Duration::from_millis(5));
+            }
+        }
+    });
+
+    while !reader.is_finished() {
+        connection.lock().unwrap().update();
+        thread::sleep(Duration::from_millis(5));
+    }
+    let (player, game) = reader.join().unwrap();
+    assert_eq!(player, "Player1");
+    assert_eq!(game, "Test Game");
+}
+
+/// Updates `connection` until it's connected.
+fn wait_for_connect(connection: &mut Connection) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !connection.is_connected() {
+        assert!(
+            Instant::now() < deadline,
+            "timed out waiting for connection: {:?}",
+            connection.error()
+        );
+        connection.update();
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn error_after_server_closes() {
+    let server = Server::start(Room::default());
+    let mut connection = Connection::new(
+        server.url.clone(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().no_cache(),
+    );
+    wait_for_connect(&mut connection);
+    assert!(connection.error().is_none());
+
+    server.accept().close();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !connection.is_disconnected() {
+        assert!(
+            Instant::now() < deadline,
+            "timed out waiting for disconnect"
+        );
+        connection.update();
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    // error() borrows the same error that err() and into_err() return, and
+    // can be called any number of times.
+    let error = connection.error().unwrap();
+    assert!(std::ptr::eq(error, connection.err()));
+    let message = error.to_string();
+    assert_eq!(connection.error().unwrap().to_string(), message);
+    assert_eq!(connection.into_err().to_string(), message);
+}
+
+#[test]
+fn take_client() {
+    let server = Server::start(Room::default());
+    let mut connection = Connection::new(
+        server.url.clone(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().no_cache(),
+    );
+    assert!(connection.take_client().is_none());
+    assert!(connection.is_connecting());
+
+    wait_for_connect(&mut connection);
+    let mut peer = server.accept();
+    peer.answer_race_mode(0);
+    let mut client = connection.take_client().unwrap();
+    assert!(connection.is_disconnected());
+    assert!(matches!(
+        connection.error(),
+        Some(Error::ClientDisconnected)
+    ));
+    assert!(connection.take_client().is_none());
+
+    // The client keeps the socket, so it can still talk to the server.
+    catch_up(&mut client);
+    client.say("hello".into()).unwrap();
+    assert_eq!(peer.expect("Say")["text"], "hello");
+}
+
+#[test]
+fn memory_cache() {
+    let server = Server::start(Room::default());
+    let cache = Cache::memory();
+    let options = || ConnectionOptions::new().cache(cache.clone());
+
+    let mut connection =
+        Connection::new(server.url.clone(), "Player1", Some("Test Game"), options());
+    wait_for_connect(&mut connection);
+    let peer = server.accept();
+    assert_eq!(peer.data_package_requests.len(), 2);
+    assert_eq!(smol::block_on(cache.stats()).unwrap().games(), 2);
+
+    // A second connection loads the data packages from the same cache.
+    let mut connection =
+        Connection::new(server.url.clone(), "Player1", Some("Test Game"), options());
+    wait_for_connect(&mut connection);
+    let peer = server.accept();
+    assert!(peer.data_package_requests.is_empty());
+    let client = connection.client().unwrap();
+    assert_eq!(client.this_game().assert_item(1).name(), "Sword");
+}
+
+#[test]
+fn no_cache_downloads_everything() {
+    let server = Server::start(Room::default());
+    let cache = Cache::memory();
+
+    let mut connection = Connection::new(
+        server.url.clone(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().cache(cache.clone()),
+    );
+    wait_for_connect(&mut connection);
+    mem::drop(server.accept());
+
+    // Even though the cache is warm, no_cache overrides it.
+    let mut connection = Connection::new(
+        server.url.clone(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().cache(cache.clone()).no_cache(),
+    );
+    wait_for_connect(&mut connection);
+    let peer = server.accept();
+    assert_eq!(peer.data_package_requests.len(), 2);
+    let client = connection.client().unwrap();
+    assert_eq!(client.this_game().assert_item(1).name(), "Sword");
+
+    // And a later cache() call overrides no_cache in turn.
+    let mut connection = Connection::new(
+        server.url.clone(),
+        "Player1",
+        Some("Test Game"),
+        ConnectionOptions::new().no_cache().cache(cache.clone()),
+    );
+    wait_for_connect(&mut connection);
+    let peer = server.accept();
+    assert!(peer.data_package_requests.is_empty());
+}